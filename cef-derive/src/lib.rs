@@ -0,0 +1,51 @@
+//! Derive macros for the `cef` crate.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Lit, Meta, NestedMeta};
+
+/// Implements `cef::message::CefMessage` for a type that also implements
+/// serde's `Serialize` and `Deserialize`.
+///
+/// The message name defaults to the name of the type. Use
+/// `#[cef_message(name = "...")]` to choose a different one.
+#[proc_macro_derive(CefMessage, attributes(cef_message))]
+pub fn derive_cef_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = match message_name(&input) {
+        Ok(name) => name.unwrap_or_else(|| input.ident.to_string()),
+        Err(error) => return error.to_compile_error().into(),
+    };
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        impl #impl_generics ::cef::message::CefMessage for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+        }
+    };
+    expanded.into()
+}
+
+fn message_name(input: &DeriveInput) -> syn::Result<Option<String>> {
+    let mut name = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path.is_ident("cef_message")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected #[cef_message(name = \"...\")]")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(ref pair)) if pair.path.is_ident("name") => {
+                    match &pair.lit {
+                        Lit::Str(value) => name = Some(value.value()),
+                        lit => return Err(syn::Error::new_spanned(lit, "expected a string literal")),
+                    }
+                }
+                nested => return Err(syn::Error::new_spanned(nested, "unknown cef_message attribute")),
+            }
+        }
+    }
+    Ok(name)
+}
@@ -0,0 +1,210 @@
+//! Starts a `Server` on a free localhost port and talks to it with a plain
+//! `TcpStream`: two HTTP requests that should get a 200 and a 404, and a
+//! WebSocket connection whose messages are echoed back. Exits with a non-zero
+//! status if any of the checks fails.
+
+use cef::{
+    app::{App, AppCallbacks},
+    logging::Logger,
+    request::Request,
+    server::{Server, ServerHandlerCallbacks, WebSocketRequestCallback},
+    settings::{LogSeverity, Settings},
+    task::{TaskRunner, ThreadId},
+};
+use log::info;
+use parking_lot::Mutex;
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    sync::mpsc::{self, Sender},
+    thread,
+    time::Duration,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const GREETING: &[u8] = b"Hello from CEF";
+/// The key and accept value from the example in RFC 6455, section 1.3.
+const WEB_SOCKET_KEY: &str = "dGhlIHNhbXBsZSBub25jZQ==";
+const WEB_SOCKET_ACCEPT: &str = "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=";
+
+pub struct AppCallbacksImpl {}
+
+impl AppCallbacks for AppCallbacksImpl {}
+
+/// Answers `/hello` with a greeting, everything else with a 404, and echoes
+/// WebSocket messages.
+pub struct EchoServer {
+    /// Receives the server once it's running, or None if it failed to start.
+    started: Mutex<Sender<Option<Server>>>,
+}
+
+impl ServerHandlerCallbacks for EchoServer {
+    fn on_server_created(&self, server: Server) {
+        let running = server.is_running();
+        self.started.lock().send(if running { Some(server) } else { None }).ok();
+    }
+    fn on_server_destroyed(&self, _server: Server) {
+        TaskRunner::post_task_on(ThreadId::UI, || cef::quit_message_loop().unwrap());
+    }
+    fn on_http_request(&self, server: Server, connection_id: i32, client_address: &str, request: Request) {
+        info!("{} {} from {}", request.get_method(), request.get_url(), client_address);
+        if request.get_url().ends_with("/hello") {
+            server.send_http_200_response(connection_id, "text/plain", GREETING);
+        } else {
+            server.send_http_404_response(connection_id);
+        }
+    }
+    fn on_web_socket_request(
+        &self,
+        _server: Server,
+        _connection_id: i32,
+        _client_address: &str,
+        _request: Request,
+        callback: WebSocketRequestCallback,
+    ) {
+        callback.accept();
+    }
+    fn on_web_socket_message(&self, server: Server, connection_id: i32, data: &[u8]) {
+        server.send_web_socket_message(connection_id, data);
+    }
+}
+
+fn connect(address: &str) -> Result<TcpStream, String> {
+    let stream = TcpStream::connect(address).map_err(|error| format!("can't connect to {}: {}", address, error))?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(|error| error.to_string())?;
+    Ok(stream)
+}
+
+/// Sends a GET request and returns the whole response. The server closes the
+/// connection after responding.
+fn http_get(address: &str, path: &str) -> Result<Vec<u8>, String> {
+    let mut stream = connect(address)?;
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, address).map_err(|error| error.to_string())?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).map_err(|error| format!("GET {}: {}", path, error))?;
+    Ok(response)
+}
+
+fn check_http(address: &str) -> Result<(), String> {
+    let response = http_get(address, "/hello")?;
+    if !response.starts_with(b"HTTP/1.1 200") || !response.ends_with(GREETING) {
+        return Err(format!("unexpected response to /hello: {}", String::from_utf8_lossy(&response)));
+    }
+    let response = http_get(address, "/missing")?;
+    if !response.starts_with(b"HTTP/1.1 404") {
+        return Err(format!("unexpected response to /missing: {}", String::from_utf8_lossy(&response)));
+    }
+    Ok(())
+}
+
+/// Reads the response head up to the empty line that ends it.
+fn read_head(stream: &mut TcpStream) -> Result<String, String> {
+    let mut head = Vec::new();
+    let mut byte = [0];
+    while !head.ends_with(b"\r\n\r\n") {
+        stream.read_exact(&mut byte).map_err(|error| format!("reading handshake response: {}", error))?;
+        head.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Sends a masked text frame. Clients must mask every frame they send.
+fn write_frame(stream: &mut TcpStream, payload: &[u8]) -> Result<(), String> {
+    assert!(payload.len() < 126);
+    let mask = [0x12, 0x34, 0x56, 0x78];
+    let mut frame = vec![0x81, 0x80 | payload.len() as u8];
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(index, byte)| byte ^ mask[index % 4]));
+    stream.write_all(&frame).map_err(|error| error.to_string())
+}
+
+/// Reads a short unmasked frame, as sent by the server.
+fn read_frame(stream: &mut TcpStream) -> Result<Vec<u8>, String> {
+    let mut header = [0; 2];
+    stream.read_exact(&mut header).map_err(|error| format!("reading frame: {}", error))?;
+    if header[0] & 0x80 == 0 || header[1] & 0x80 != 0 || header[1] & 0x7f >= 126 {
+        return Err(format!("unexpected frame header {:02x} {:02x}", header[0], header[1]));
+    }
+    let mut payload = vec![0; (header[1] & 0x7f) as usize];
+    stream.read_exact(&mut payload).map_err(|error| format!("reading frame: {}", error))?;
+    Ok(payload)
+}
+
+fn check_web_socket(address: &str) -> Result<(), String> {
+    let mut stream = connect(address)?;
+    write!(
+        stream,
+        "GET /echo HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        address, WEB_SOCKET_KEY,
+    )
+    .map_err(|error| error.to_string())?;
+    let head = read_head(&mut stream)?;
+    let accepted = head.starts_with("HTTP/1.1 101")
+        && head.lines().any(|line| {
+            let mut parts = line.splitn(2, ':');
+            let name = parts.next().unwrap_or("");
+            let value = parts.next().unwrap_or("").trim();
+            name.eq_ignore_ascii_case("Sec-WebSocket-Accept") && value == WEB_SOCKET_ACCEPT
+        });
+    if !accepted {
+        return Err(format!("WebSocket handshake failed: {}", head));
+    }
+    for message in &[&b"ping"[..], b"second message"] {
+        write_frame(&mut stream, message)?;
+        let echo = read_frame(&mut stream)?;
+        if echo != *message {
+            return Err(format!(
+                "sent {:?} but got {:?} back",
+                String::from_utf8_lossy(message),
+                String::from_utf8_lossy(&echo)
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let app = App::new(AppCallbacksImpl {});
+    let result = cef::execute_process(Some(app.clone()), None);
+    if result >= 0 {
+        std::process::exit(result);
+    }
+
+    let settings = Settings::new().log_severity(LogSeverity::Warning);
+    let context = cef::Context::initialize(settings, Some(app), None).unwrap();
+    let logger = Box::new(Logger::builder().level(log::LevelFilter::Info).build());
+    log::set_boxed_logger(logger).map(|()| log::set_max_level(log::LevelFilter::Info)).unwrap();
+
+    let (started, server) = mpsc::channel();
+    // Port 0 binds to any free port; the chosen one is part of the address.
+    Server::create("127.0.0.1", 0, 10, EchoServer { started: Mutex::new(started) });
+
+    let client = thread::spawn(move || {
+        let server = match server.recv_timeout(TIMEOUT) {
+            Ok(Some(server)) => server,
+            Ok(None) => return Err("the server failed to start".to_owned()),
+            Err(error) => {
+                TaskRunner::post_task_on(ThreadId::UI, || cef::quit_message_loop().unwrap());
+                return Err(format!("the server didn't start: {}", error));
+            }
+        };
+        let address = server.get_address();
+        info!("Server listening on {}", address);
+        let result = check_http(&address).and_then(|()| check_web_socket(&address));
+        server.shutdown();
+        result
+    });
+
+    context.run_message_loop();
+
+    let result = client.join().unwrap();
+    drop(context);
+    match result {
+        Ok(()) => println!("All server checks passed"),
+        Err(error) => {
+            eprintln!("Server check failed: {}", error);
+            std::process::exit(1);
+        }
+    }
+}
@@ -2,7 +2,7 @@ use crate::{
     browser_process_handler::{BrowserProcessHandler},
     command_line::CommandLine,
     refcounted::{RefCountedPtr, Wrapper},
-    render_process_handler::{RenderProcessHandler},
+    render_process_handler::{RenderProcessHandler, DefaultRenderProcessHandler},
     resource_bundle_handler::{ResourceBundleHandler},
     scheme_registrar::SchemeRegistrar,
     string::CefString,
@@ -121,7 +121,9 @@ cef_callback_impl! {
             self.0.get_browser_process_handler().map(|cef| cef.into_raw()).unwrap_or(null_mut())
         }
         fn get_render_process_handler(&self) -> *mut cef_render_process_handler_t {
-            self.0.get_render_process_handler().map(|cef| cef.into_raw()).unwrap_or(null_mut())
+            self.0.get_render_process_handler()
+                .unwrap_or_else(|| RenderProcessHandler::new(DefaultRenderProcessHandler))
+                .into_raw()
         }
     }
 }
@@ -6,10 +6,12 @@ use crate::{
     registration::Registration,
     drag::{DragData, DragOperation},
     events::{KeyEvent, MouseButtonType, MouseEvent, TouchEvent},
+    executor::CallbackFuture,
     extension::Extension,
     file_dialog::{FileDialogMode, RunFileDialogCallbackWrapper},
     image::Image,
     ime::CompositionUnderline,
+    load_handler::TransitionType,
     navigation::NavigationEntry,
     printing::PDFPrintSettings,
     refcounted::{RefCountedPtr, Wrapper},
@@ -25,6 +27,8 @@ use cef_sys::{
     cef_navigation_entry_visitor_t, cef_paint_element_type_t, cef_pdf_print_callback_t,
     cef_string_t,
 };
+use chrono::{DateTime, Utc};
+use futures::channel::oneshot;
 use parking_lot::Mutex;
 use std::{
     collections::HashMap,
@@ -306,6 +310,22 @@ impl BrowserHost {
             }
         }
     }
+    /// Async version of [BrowserHost::download_image]. Resolves with the
+    /// resulting HTTP status code and image, which is None if the download
+    /// failed.
+    pub fn download_image_async(
+        &self,
+        image_url: &str,
+        is_favicon: bool,
+        max_image_size: u32,
+        bypass_cache: bool,
+    ) -> CallbackFuture<(u16, Option<Image>)> {
+        let (sender, future) = CallbackFuture::new();
+        self.download_image(image_url, is_favicon, max_image_size, bypass_cache, move |_, status, image| {
+            let _ = sender.send((status, image));
+        });
+        future
+    }
     /// Print the current browser contents.
     pub fn print(&self) {
         if let Some(print) = self.0.print {
@@ -339,6 +359,19 @@ impl BrowserHost {
             }
         }
     }
+    /// Async version of [BrowserHost::print_to_pdf]. Resolves with true if the
+    /// printing completed successfully or false otherwise.
+    pub fn print_to_pdf_async(
+        &self,
+        path: &str,
+        settings: &PDFPrintSettings,
+    ) -> CallbackFuture<bool> {
+        let (sender, future) = CallbackFuture::new();
+        self.print_to_pdf(path, settings, move |_, ok| {
+            let _ = sender.send(ok);
+        });
+        future
+    }
     /// Search for `searchText`. `identifier` must be a unique ID and these IDs
     /// must strictly increase so that newer requests always have greater IDs than
     /// older requests. If `identifier` is zero or less than the previous ID value
@@ -474,21 +507,26 @@ impl BrowserHost {
     /// the DevTools protocol documentation (linked above) for details of supported
     /// functions and the expected `params` dictionary contents. This function will
     /// return the assigned message ID if called on the UI thread and the message
-    /// was successfully submitted for validation, otherwise 0. See the
+    /// was successfully submitted for validation, otherwise None. See the
     /// `send_dev_tools_message` documentation for additional usage information.
     pub fn execute_dev_tools_method(
         &self,
         message_id: i32,
         method: &str,
         params: Option<DictionaryValue>,
-    ) -> bool {
-        unsafe {
+    ) -> Option<i32> {
+        let message_id = unsafe {
             self.0.execute_dev_tools_method.unwrap()(
                 self.as_ptr(),
                 message_id,
                 CefString::from(method).as_ptr(),
                 params.map(|p| p.into_raw()).unwrap_or_else(null_mut),
-            ) != 0
+            )
+        };
+        if message_id != 0 {
+            Some(message_id)
+        } else {
+            None
         }
     }
     /// Add an observer for DevTools protocol messages (function results and
@@ -529,6 +567,24 @@ impl BrowserHost {
             }
         }
     }
+    /// Async version of [BrowserHost::get_navigation_entries]. Navigation entries
+    /// are only valid during the visit, so this resolves with snapshots of their
+    /// values instead.
+    pub fn get_navigation_entries_async(
+        &self,
+        current_only: bool,
+    ) -> CallbackFuture<Vec<NavigationEntrySnapshot>> {
+        let (sender, future) = CallbackFuture::new();
+        let mut collector = NavigationEntryCollector {
+            entries: Vec::new(),
+            sender: Some(sender),
+        };
+        self.get_navigation_entries(NavigationEntryVisitor::new(move |visit: NavigationEntryVisit| {
+            collector.entries.push(NavigationEntrySnapshot::new(&visit.entry, visit.current));
+            collector.sender.as_ref().map(|sender| !sender.is_canceled()).unwrap_or(false)
+        }), current_only);
+        future
+    }
     /// Set whether mouse cursor change is disabled.
     pub fn set_mouse_cursor_change_disabled(&self, disabled: bool) {
         if let Some(set_mouse_cursor_change_disabled) = self.0.set_mouse_cursor_change_disabled {
@@ -1091,6 +1147,61 @@ pub struct NavigationEntryVisit {
     pub total: usize,
 }
 
+/// The values of a [NavigationEntry] at the time it was visited.
+#[derive(Clone, Debug)]
+pub struct NavigationEntrySnapshot {
+    /// The actual URL of the page.
+    pub url: String,
+    /// The URL to display to the user.
+    pub display_url: String,
+    /// The original URL that was entered by the user before any redirects.
+    pub original_url: String,
+    /// The title set by the page.
+    pub title: String,
+    /// The transition type which indicates what the user did to move to this page.
+    pub transition_type: TransitionType,
+    /// Whether this navigation includes post data.
+    pub has_post_data: bool,
+    /// The time for the last known successful navigation completion.
+    pub completion_time: DateTime<Utc>,
+    /// The HTTP status code for the last known successful navigation response,
+    /// or 0 if the response has not yet been received.
+    pub http_status_code: u16,
+    /// Whether or not this was the currently loaded navigation entry.
+    pub current: bool,
+}
+
+impl NavigationEntrySnapshot {
+    fn new(entry: &NavigationEntry, current: bool) -> NavigationEntrySnapshot {
+        NavigationEntrySnapshot {
+            url: entry.get_url(),
+            display_url: entry.get_display_url(),
+            original_url: entry.get_original_url(),
+            title: entry.get_title(),
+            transition_type: entry.get_transition_type(),
+            has_post_data: entry.has_post_data(),
+            completion_time: entry.get_completion_time(),
+            http_status_code: entry.get_http_status_code(),
+            current,
+        }
+    }
+}
+
+/// Collects visited entries and hands them over once CEF releases the visitor,
+/// which also covers the case where no entries are visited at all.
+struct NavigationEntryCollector {
+    entries: Vec<NavigationEntrySnapshot>,
+    sender: Option<oneshot::Sender<Vec<NavigationEntrySnapshot>>>,
+}
+
+impl Drop for NavigationEntryCollector {
+    fn drop(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(std::mem::replace(&mut self.entries, Vec::new()));
+        }
+    }
+}
+
 /// Callback type for `NavigationEntryVisitor`.
 ///
 /// Returns whether or not to continue visiting more navigation entries.
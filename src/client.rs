@@ -39,9 +39,9 @@ use self::{
     focus_handler::FocusHandler,
     js_dialog_handler::JsDialogHandler,
     keyboard_handler::KeyboardHandler,
-    life_span_handler::LifeSpanHandler,
+    life_span_handler::{LifeSpanHandler, DefaultLifeSpanHandler},
     render_handler::RenderHandler,
-    request_handler::RequestHandler,
+    request_handler::{RequestHandler, DefaultRequestHandler},
 };
 
 use crate::{
@@ -186,7 +186,9 @@ cef_callback_impl! {
             self.0.get_keyboard_handler().map(|cef| cef.into_raw()).unwrap_or(null_mut())
         }
         fn get_life_span_handler(&self) -> *mut cef_life_span_handler_t {
-            self.0.get_life_span_handler().map(|cef| cef.into_raw()).unwrap_or(null_mut())
+            self.0.get_life_span_handler()
+                .unwrap_or_else(|| LifeSpanHandler::new(DefaultLifeSpanHandler))
+                .into_raw()
         }
         fn get_load_handler(&self) -> *mut cef_load_handler_t {
             self.0.get_load_handler().map(|cef| cef.into_raw()).unwrap_or(null_mut())
@@ -195,7 +197,9 @@ cef_callback_impl! {
             self.0.get_render_handler().map(|cef| cef.into_raw()).unwrap_or(null_mut())
         }
         fn get_request_handler(&self) -> *mut cef_request_handler_t {
-            self.0.get_request_handler().map(|cef| cef.into_raw()).unwrap_or(null_mut())
+            self.0.get_request_handler()
+                .unwrap_or_else(|| RequestHandler::new(DefaultRequestHandler))
+                .into_raw()
         }
         fn process_message_received(
             &self,
@@ -205,7 +209,24 @@ cef_callback_impl! {
             message       : ProcessMessage: *mut cef_process_message_t
         ) -> std::os::raw::c_int {
             assert_eq!(ProcessId::Renderer, source_process);
-            self.0.on_process_message_received(browser, frame, message) as std::os::raw::c_int
+            if crate::evaluate::on_browser_process_message(&message)
+                || crate::message_router::on_browser_process_message(&browser, &frame, &message) {
+                return 1;
+            }
+            #[cfg(feature = "typed-messages")]
+            {
+                if crate::message::dispatch(&browser, &frame, &message) {
+                    return 1;
+                }
+            }
+            let handled = self.0.on_process_message_received(browser, frame, message.clone());
+            #[cfg(feature = "typed-messages")]
+            {
+                if !handled {
+                    crate::message::log_unhandled(&message);
+                }
+            }
+            handled as std::os::raw::c_int
         }
     }
 }
@@ -181,6 +181,12 @@ pub trait LifeSpanHandlerCallbacks: 'static + Send + Sync {
     fn on_before_close(&self, browser: Browser) {}
 }
 
+/// Used when the client doesn't provide a life span handler, so that the crate's
+/// built-in bookkeeping still gets notified about closing browsers.
+pub(crate) struct DefaultLifeSpanHandler;
+
+impl LifeSpanHandlerCallbacks for DefaultLifeSpanHandler {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PopupFeatures {
     pub x: Option<i32>,
@@ -292,6 +298,8 @@ cef_callback_impl!{
             self.0.do_close(browser) as _
         }
         fn on_before_close(&self, browser: Browser: *mut cef_browser_t) {
+            crate::evaluate::fail_browser(&browser);
+            crate::message_router::on_browser_gone(&browser);
             self.0.on_before_close(browser.clone());
             unsafe{ browser.poison(); }
         }
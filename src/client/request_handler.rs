@@ -238,6 +238,13 @@ pub trait RequestHandlerCallbacks: Sync + Send + 'static {
     fn on_document_available_in_main_frame(&self, browser: Browser) {}
 }
 
+/// Used when the client doesn't provide a request handler, so that the crate's
+/// built-in bookkeeping still gets notified about navigations and terminated
+/// render processes.
+pub(crate) struct DefaultRequestHandler;
+
+impl RequestHandlerCallbacks for DefaultRequestHandler {}
+
 #[repr(transparent)]
 struct RequestHandlerWrapper(Box<dyn RequestHandlerCallbacks>);
 
@@ -280,7 +287,11 @@ cef_callback_impl!{
             user_gesture: bool: c_int,
             is_redirect: bool: c_int
         ) -> c_int {
-            self.0.on_before_browse(browser, frame, request, user_gesture, is_redirect) as c_int
+            let cancel = self.0.on_before_browse(browser.clone(), frame.clone(), request, user_gesture, is_redirect);
+            if !cancel {
+                crate::message_router::on_before_browse(&browser, &frame);
+            }
+            cancel as c_int
         }
         fn on_open_url_from_tab(
             &self,
@@ -393,6 +404,8 @@ cef_callback_impl!{
             browser: Browser: *mut cef_browser_t,
             status: TerminationStatus: cef_termination_status_t::Type
         ) {
+            crate::evaluate::fail_browser(&browser);
+            crate::message_router::on_browser_gone(&browser);
             self.0.on_render_process_terminated(browser, status);
         }
         fn on_document_available_in_main_frame(
@@ -8,9 +8,11 @@ use cef_sys::cef_set_cookie_callback_t;
 use cef_sys::cef_delete_cookies_callback_t;
 use cef_sys::{cef_cookie_t, cef_cookie_manager_t, cef_cookie_priority_t, cef_cookie_same_site_t};
 use chrono::{DateTime, Utc};
+use futures::channel::oneshot;
 
 use crate::{
     callback::CompletionCallback,
+    executor::CallbackFuture,
     string::CefString,
 };
 
@@ -186,6 +188,18 @@ impl CookieManager {
             )
         }
     }
+    /// Async version of [CookieManager::set_supported_schemes].
+    pub fn set_supported_schemes_async<'a>(
+        &self,
+        schemes: impl IntoIterator<Item=&'a str>,
+        include_defaults: bool,
+    ) -> CallbackFuture<()> {
+        let (sender, future) = CallbackFuture::new();
+        self.set_supported_schemes(schemes, include_defaults, move || {
+            let _ = sender.send(());
+        });
+        future
+    }
     /// Visit all cookies on the UI thread. The returned cookies are ordered by
     /// longest path, then by earliest creation date. Returns `false` if cookies
     /// cannot be accessed.
@@ -200,6 +214,13 @@ impl CookieManager {
             ) != 0
         }
     }
+    /// Async version of [CookieManager::visit_all_cookies] that collects all
+    /// cookies. Resolves with an empty list if cookies cannot be accessed.
+    pub fn visit_all_cookies_async(&self) -> CallbackFuture<Vec<Cookie>> {
+        let (sender, future) = CallbackFuture::new();
+        self.visit_all_cookies(CookieCollector::new(sender).into_visitor());
+        future
+    }
     /// Visit a subset of cookies on the UI thread. The results are filtered by the
     /// given url scheme, host, domain and path. If `include_http_only` is `true`
     /// HTTP-only cookies will also be included in the results. The returned
@@ -220,6 +241,18 @@ impl CookieManager {
             ) != 0
         }
     }
+    /// Async version of [CookieManager::visit_url_cookies] that collects all
+    /// matching cookies. Resolves with an empty list if cookies cannot be
+    /// accessed.
+    pub fn visit_url_cookies_async(
+        &self,
+        url: &str,
+        include_http_only: bool,
+    ) -> CallbackFuture<Vec<Cookie>> {
+        let (sender, future) = CallbackFuture::new();
+        self.visit_url_cookies(url, include_http_only, CookieCollector::new(sender).into_visitor());
+        future
+    }
     /// Sets a cookie given a valid URL and explicit user-provided cookie
     /// attributes. This function expects each attribute to be well-formed. It will
     /// check for disallowed characters (e.g. the ';' character is disallowed
@@ -242,6 +275,16 @@ impl CookieManager {
             ) != 0
         }
     }
+    /// Async version of [CookieManager::set_cookie]. Resolves with whether the
+    /// cookie was set, or with [Canceled](crate::executor::Canceled) if the URL
+    /// is invalid or cookies cannot be accessed.
+    pub fn set_cookie_async(&self, url: &str, cookie: &Cookie) -> CallbackFuture<bool> {
+        let (sender, future) = CallbackFuture::new();
+        self.set_cookie(url, cookie, move |success| {
+            let _ = sender.send(success);
+        });
+        future
+    }
     /// Delete all cookies that match the specified parameters. If both `url` and
     /// `cookie_name` values are specified all host and domain cookies matching
     /// both will be deleted. If only `url` is specified all host cookies (but not
@@ -266,6 +309,16 @@ impl CookieManager {
             ) != 0
         }
     }
+    /// Async version of [CookieManager::delete_cookies]. Resolves with the number
+    /// of deleted cookies, or with [Canceled](crate::executor::Canceled) if the
+    /// URL is invalid or cookies cannot be accessed.
+    pub fn delete_cookies_async(&self, url: &str, cookie_name: &str) -> CallbackFuture<usize> {
+        let (sender, future) = CallbackFuture::new();
+        self.delete_cookies(url, cookie_name, move |num_deleted| {
+            let _ = sender.send(num_deleted);
+        });
+        future
+    }
     /// Flush the backing store (if any) to disk. If `callback` is non-NULL it will
     /// be executed asnychronously on the UI thread after the flush is complete.
     /// Returns `false` if cookies cannot be accessed.
@@ -280,6 +333,45 @@ impl CookieManager {
             ) != 0
         }
     }
+    /// Async version of [CookieManager::flush_store]. Resolves with
+    /// [Canceled](crate::executor::Canceled) if cookies cannot be accessed.
+    pub fn flush_store_async(&self) -> CallbackFuture<()> {
+        let (sender, future) = CallbackFuture::new();
+        self.flush_store(move || {
+            let _ = sender.send(());
+        });
+        future
+    }
+}
+
+/// Collects visited cookies and hands them over once CEF releases the visitor,
+/// which also covers the case where no cookies are visited at all.
+struct CookieCollector {
+    cookies: Vec<Cookie>,
+    sender: Option<oneshot::Sender<Vec<Cookie>>>,
+}
+
+impl CookieCollector {
+    fn new(sender: oneshot::Sender<Vec<Cookie>>) -> CookieCollector {
+        CookieCollector {
+            cookies: Vec::new(),
+            sender: Some(sender),
+        }
+    }
+    fn into_visitor(mut self) -> impl CookieVisitorFn {
+        move |visit: CookieVisit| {
+            self.cookies.push(visit.cookie);
+            self.sender.as_ref().map(|sender| !sender.is_canceled()).unwrap_or(false)
+        }
+    }
+}
+
+impl Drop for CookieCollector {
+    fn drop(&mut self) {
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(std::mem::replace(&mut self.cookies, Vec::new()));
+        }
+    }
 }
 
 impl CookieVisitor {
@@ -314,7 +406,7 @@ cef_callback_impl!{
         ) -> c_int {
             let cookie = unsafe{ Cookie::new(cookie) };
             let mut delete_cookie_rs = *delete_cookie != 0;
-            let ret = self.0.lock().take().unwrap()(CookieVisit {
+            let ret = self.0.lock().as_mut().unwrap()(CookieVisit {
                 cookie,
                 index: count as usize,
                 len: total as usize,
@@ -0,0 +1,436 @@
+//! A correlated, typed session on top of the raw DevTools protocol API.
+//!
+//! [BrowserHost::execute_dev_tools_method] and [DevToolsMessageObserverCallbacks]
+//! only deal with message ids and raw JSON bytes. [DevToolsSession] matches
+//! method results to their calls, dispatches events to subscribers and keeps
+//! track of which protocol domains need to be enabled.
+//!
+//! ```rust,ignore
+//! let session = DevToolsSession::new(browser.get_host());
+//! let mut requests = session.subscribe_typed::<network::RequestWillBeSent>();
+//! session.execute(&page::Navigate::new("https://example.com")).await?;
+//! while let Some(event) = requests.next().await {
+//!     println!("{}", event?.request.url);
+//! }
+//! ```
+//!
+//! This module requires the `devtools` feature.
+
+use crate::{
+    browser::Browser,
+    browser_host::BrowserHost,
+    devtools_message_observer::{DevToolsMessageObserver, DevToolsMessageObserverCallbacks},
+    registration::Registration,
+    task::{TaskRunner, ThreadId},
+    values::{DictionaryValue, ListValue},
+};
+use futures::{
+    channel::{mpsc, oneshot},
+    stream::Stream,
+};
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+pub mod dom;
+pub mod network;
+pub mod page;
+pub mod runtime;
+
+/// Errors that can occur when calling a DevTools protocol method.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DevToolsError {
+    /// The method call could not be submitted. This happens if it wasn't made on
+    /// the browser process UI thread, or if the parameters weren't a JSON object.
+    NotSent,
+    /// The DevTools protocol returned an error for the method call.
+    Protocol {
+        code: i64,
+        message: String,
+        data: Option<String>,
+    },
+    /// The DevTools agent detached, or the session was dropped, before the
+    /// result arrived.
+    Detached,
+    /// The parameters or the result could not be converted from or to JSON.
+    Json(String),
+}
+
+impl fmt::Display for DevToolsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DevToolsError::NotSent => write!(f, "DevTools method call could not be submitted"),
+            DevToolsError::Protocol { code, message, .. } => write!(f, "DevTools protocol error {}: {}", code, message),
+            DevToolsError::Detached => write!(f, "DevTools agent detached before the result arrived"),
+            DevToolsError::Json(message) => write!(f, "DevTools JSON error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for DevToolsError {}
+
+impl From<serde_json::Error> for DevToolsError {
+    fn from(error: serde_json::Error) -> Self {
+        DevToolsError::Json(error.to_string())
+    }
+}
+
+/// A DevTools protocol method with typed parameters and result.
+pub trait Method: Serialize {
+    /// The fully qualified method name, for example `Page.navigate`.
+    const NAME: &'static str;
+    /// The type the method's result is deserialized into.
+    type Returns: DeserializeOwned;
+}
+
+/// A DevTools protocol event with typed parameters.
+pub trait Event: DeserializeOwned {
+    /// The fully qualified event name, for example `Network.requestWillBeSent`.
+    const NAME: &'static str;
+}
+
+/// The result of methods that don't return anything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Empty {}
+
+/// An untyped DevTools protocol event.
+#[derive(Debug, Clone)]
+pub struct DevToolsEvent {
+    /// The fully qualified event name.
+    pub method: String,
+    /// The event parameters. This is an empty object if the event has none.
+    pub params: Value,
+}
+
+/// A session for exchanging DevTools protocol messages with a browser.
+///
+/// The session registers a [DevToolsMessageObserver] with the browser and stays
+/// registered until it is dropped. Pending calls resolve with
+/// [DevToolsError::Detached] and event streams end when the session is dropped.
+///
+/// Methods must be called on the browser process UI thread.
+pub struct DevToolsSession {
+    inner: Arc<SessionInner>,
+    _registration: Registration,
+}
+
+impl DevToolsSession {
+    /// Creates a new session for the browser represented by `host`.
+    pub fn new(host: BrowserHost) -> DevToolsSession {
+        let inner = Arc::new(SessionInner {
+            host: host.clone(),
+            pending: Mutex::new(HashMap::new()),
+            subscribers: Mutex::new(HashMap::new()),
+            domains: Mutex::new(HashMap::new()),
+        });
+        let registration = host.add_dev_tools_message_observer(DevToolsMessageObserver::new(SessionObserver(inner.clone())));
+        DevToolsSession {
+            inner,
+            _registration: registration,
+        }
+    }
+    /// Calls the DevTools protocol method `method` with the optional `params`
+    /// object. Resolves with the `result` object of the response.
+    pub fn call(&self, method: &str, params: Option<Value>) -> impl Future<Output = Result<Value, DevToolsError>> {
+        let receiver = self.inner.call(method, params);
+        async move {
+            receiver.await.unwrap_or(Err(DevToolsError::Detached))
+        }
+    }
+    /// Calls a typed DevTools protocol method. See the [dom], [network], [page]
+    /// and [runtime] modules for predefined methods.
+    pub fn execute<M: Method>(&self, method: &M) -> impl Future<Output = Result<M::Returns, DevToolsError>> {
+        let receiver = serde_json::to_value(method)
+            .map(|params| self.inner.call(M::NAME, Some(params)))
+            .map_err(DevToolsError::from);
+        async move {
+            let result = receiver?.await.unwrap_or(Err(DevToolsError::Detached))?;
+            Ok(serde_json::from_value(result)?)
+        }
+    }
+    /// Subscribes to the event named `event`, for example
+    /// `Network.requestWillBeSent`. The event's domain is enabled as long as at
+    /// least one subscription or [DomainGuard] for it exists.
+    pub fn subscribe(&self, event: &str) -> EventStream {
+        let (sender, receiver) = mpsc::unbounded();
+        self.inner.subscribers.lock()
+            .entry(event.to_owned())
+            .or_insert_with(Vec::new)
+            .push(sender);
+        let domain = event.split('.').next().unwrap_or(event);
+        EventStream {
+            receiver,
+            _domain: self.enable_domain(domain),
+        }
+    }
+    /// Subscribes to a typed event. See [DevToolsSession::subscribe].
+    pub fn subscribe_typed<E: Event>(&self) -> TypedEventStream<E> {
+        TypedEventStream {
+            stream: self.subscribe(E::NAME),
+            _event: PhantomData,
+        }
+    }
+    /// Enables the protocol domain `domain` (for example `Network`) until the
+    /// returned guard is dropped. `<domain>.enable` is only sent for the first
+    /// guard and `<domain>.disable` once the last one is dropped.
+    pub fn enable_domain(&self, domain: &str) -> DomainGuard {
+        let first = {
+            let mut domains = self.inner.domains.lock();
+            let count = domains.entry(domain.to_owned()).or_insert(0);
+            *count += 1;
+            *count == 1
+        };
+        if first {
+            self.inner.call_on_ui_thread(format!("{}.enable", domain));
+        }
+        DomainGuard {
+            inner: self.inner.clone(),
+            domain: domain.to_owned(),
+        }
+    }
+}
+
+impl Drop for DevToolsSession {
+    fn drop(&mut self) {
+        self.inner.fail_pending();
+        // The streams keep `inner` alive through their domain guards, so the
+        // senders have to be dropped here for the streams to end.
+        self.inner.subscribers.lock().clear();
+    }
+}
+
+/// Keeps a protocol domain enabled. See [DevToolsSession::enable_domain].
+pub struct DomainGuard {
+    inner: Arc<SessionInner>,
+    domain: String,
+}
+
+impl Drop for DomainGuard {
+    fn drop(&mut self) {
+        let last = {
+            let mut domains = self.inner.domains.lock();
+            match domains.get_mut(&self.domain) {
+                Some(count) if *count > 1 => {
+                    *count -= 1;
+                    false
+                }
+                Some(_) => {
+                    domains.remove(&self.domain);
+                    true
+                }
+                None => false,
+            }
+        };
+        if last {
+            self.inner.call_on_ui_thread(format!("{}.disable", self.domain));
+        }
+    }
+}
+
+/// A stream of untyped events. It ends when the session is dropped. See
+/// [DevToolsSession::subscribe].
+pub struct EventStream {
+    receiver: mpsc::UnboundedReceiver<DevToolsEvent>,
+    _domain: DomainGuard,
+}
+
+impl Stream for EventStream {
+    type Item = DevToolsEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+/// A stream of typed events. Yields an error for events whose parameters
+/// couldn't be deserialized. See [DevToolsSession::subscribe_typed].
+pub struct TypedEventStream<E: Event> {
+    stream: EventStream,
+    _event: PhantomData<fn() -> E>,
+}
+
+impl<E: Event> Stream for TypedEventStream<E> {
+    type Item = Result<E, DevToolsError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.stream).poll_next(cx).map(|event| {
+            event.map(|event| serde_json::from_value(event.params).map_err(DevToolsError::from))
+        })
+    }
+}
+
+type ResultSender = oneshot::Sender<Result<Value, DevToolsError>>;
+
+struct SessionInner {
+    host: BrowserHost,
+    pending: Mutex<HashMap<i32, ResultSender>>,
+    subscribers: Mutex<HashMap<String, Vec<mpsc::UnboundedSender<DevToolsEvent>>>>,
+    domains: Mutex<HashMap<String, usize>>,
+}
+
+impl SessionInner {
+    fn call(&self, method: &str, params: Option<Value>) -> oneshot::Receiver<Result<Value, DevToolsError>> {
+        let (sender, receiver) = oneshot::channel();
+        let params = match params {
+            None | Some(Value::Null) => None,
+            Some(Value::Object(params)) => Some(to_dictionary(&params)),
+            Some(_) => {
+                let _ = sender.send(Err(DevToolsError::NotSent));
+                return receiver;
+            }
+        };
+        // Results are delivered on the UI thread, which is also the only thread
+        // the call can succeed on, so the id is always registered in time.
+        match self.host.execute_dev_tools_method(0, method, params) {
+            Some(message_id) => {
+                self.pending.lock().insert(message_id, sender);
+            }
+            None => {
+                let _ = sender.send(Err(DevToolsError::NotSent));
+            }
+        }
+        receiver
+    }
+    /// Sends a parameterless method call whose result is of no interest, from
+    /// whichever thread a subscription happens to be created or dropped on.
+    fn call_on_ui_thread(self: &Arc<Self>, method: String) {
+        if TaskRunner::currently_on(ThreadId::UI) {
+            self.call(&method, None);
+        } else {
+            let inner = self.clone();
+            TaskRunner::post_task_on(ThreadId::UI, move || {
+                inner.call(&method, None);
+            });
+        }
+    }
+    fn fail_pending(&self) {
+        for (_, sender) in self.pending.lock().drain() {
+            let _ = sender.send(Err(DevToolsError::Detached));
+        }
+    }
+}
+
+struct SessionObserver(Arc<SessionInner>);
+
+impl DevToolsMessageObserverCallbacks for SessionObserver {
+    fn on_dev_tools_method_result(
+        &mut self,
+        browser: Browser,
+        message_id: i32,
+        success: bool,
+        result: &[u8],
+    ) {
+        let sender = self.0.pending.lock().remove(&message_id);
+        if let Some(sender) = sender {
+            let result = parse_message(result).map_err(DevToolsError::from).and_then(|result| {
+                if success {
+                    Ok(result)
+                } else {
+                    let error: ProtocolError = serde_json::from_value(result)?;
+                    Err(DevToolsError::Protocol {
+                        code: error.code,
+                        message: error.message,
+                        data: error.data,
+                    })
+                }
+            });
+            let _ = sender.send(result);
+        }
+    }
+    fn on_dev_tools_event(
+        &mut self,
+        browser: Browser,
+        method: &str,
+        params: &[u8],
+    ) {
+        let mut subscribers = self.0.subscribers.lock();
+        if let Some(senders) = subscribers.get_mut(method) {
+            let params = match parse_message(params) {
+                Ok(params) => params,
+                Err(error) => {
+                    log::warn!("Failed to parse parameters of DevTools event {}: {}", method, error);
+                    return;
+                }
+            };
+            senders.retain(|sender| sender.unbounded_send(DevToolsEvent {
+                method: method.to_owned(),
+                params: params.clone(),
+            }).is_ok());
+            if senders.is_empty() {
+                subscribers.remove(method);
+            }
+        }
+    }
+    fn on_dev_tools_agent_detached(
+        &mut self,
+        browser: Browser,
+    ) {
+        self.0.fail_pending();
+    }
+}
+
+#[derive(Deserialize)]
+struct ProtocolError {
+    code: i64,
+    message: String,
+    data: Option<String>,
+}
+
+/// CEF passes no bytes at all for empty result and parameter dictionaries.
+fn parse_message(message: &[u8]) -> Result<Value, serde_json::Error> {
+    if message.is_empty() {
+        Ok(Value::Object(Map::new()))
+    } else {
+        serde_json::from_slice(message)
+    }
+}
+
+fn to_dictionary(map: &Map<String, Value>) -> DictionaryValue {
+    let dictionary = DictionaryValue::new();
+    for (key, value) in map {
+        match value {
+            Value::Null => dictionary.insert_null(key),
+            Value::Bool(value) => dictionary.insert_bool(key, *value),
+            Value::Number(value) => match as_int(value) {
+                Some(value) => dictionary.insert_int(key, value),
+                None => dictionary.insert_double(key, value.as_f64().unwrap_or_default()),
+            },
+            Value::String(value) => dictionary.insert_string(key, value),
+            Value::Array(value) => dictionary.insert_list(key, to_list(value)),
+            Value::Object(value) => dictionary.insert_dictionary(key, to_dictionary(value)),
+        };
+    }
+    dictionary
+}
+
+fn to_list(values: &[Value]) -> ListValue {
+    let list = ListValue::new();
+    for (index, value) in values.iter().enumerate() {
+        match value {
+            Value::Null => list.set_null(index),
+            Value::Bool(value) => list.set_bool(index, *value),
+            Value::Number(value) => match as_int(value) {
+                Some(value) => list.set_int(index, value),
+                None => list.set_double(index, value.as_f64().unwrap_or_default()),
+            },
+            Value::String(value) => list.set_string(index, value),
+            Value::Array(value) => list.set_list(index, to_list(value)),
+            Value::Object(value) => list.set_dictionary(index, to_dictionary(value)),
+        };
+    }
+    list
+}
+
+fn as_int(number: &serde_json::Number) -> Option<i32> {
+    use std::convert::TryFrom;
+    number.as_i64().and_then(|number| i32::try_from(number).ok())
+}
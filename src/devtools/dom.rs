@@ -0,0 +1,142 @@
+//! Methods and events of the `DOM` domain.
+
+use super::{Empty, Event, Method};
+use serde::{Deserialize, Serialize};
+
+/// Unique DOM node identifier.
+pub type NodeId = i64;
+
+/// DOM interaction is implemented in terms of mirror objects that represent the
+/// actual DOM nodes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Node {
+    pub node_id: NodeId,
+    pub parent_id: Option<NodeId>,
+    pub backend_node_id: i64,
+    pub node_type: i64,
+    pub node_name: String,
+    pub local_name: String,
+    pub node_value: String,
+    pub child_node_count: Option<i64>,
+    pub children: Option<Vec<Node>>,
+    /// Attributes of element nodes as a flat list of names and values.
+    pub attributes: Option<Vec<String>>,
+    #[serde(rename = "documentURL")]
+    pub document_url: Option<String>,
+}
+
+/// Returns the root DOM node to the caller.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetDocument {
+    /// The maximum depth at which children should be retrieved, -1 for the
+    /// entire subtree.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pierce: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetDocumentReturns {
+    pub root: Node,
+}
+
+impl Method for GetDocument {
+    const NAME: &'static str = "DOM.getDocument";
+    type Returns = GetDocumentReturns;
+}
+
+/// Executes `querySelector` on the given node.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuerySelector {
+    pub node_id: NodeId,
+    pub selector: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuerySelectorReturns {
+    pub node_id: NodeId,
+}
+
+impl Method for QuerySelector {
+    const NAME: &'static str = "DOM.querySelector";
+    type Returns = QuerySelectorReturns;
+}
+
+/// Executes `querySelectorAll` on the given node.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuerySelectorAll {
+    pub node_id: NodeId,
+    pub selector: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuerySelectorAllReturns {
+    pub node_ids: Vec<NodeId>,
+}
+
+impl Method for QuerySelectorAll {
+    const NAME: &'static str = "DOM.querySelectorAll";
+    type Returns = QuerySelectorAllReturns;
+}
+
+/// Returns the node's HTML markup.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetOuterHtml {
+    pub node_id: NodeId,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GetOuterHtmlReturns {
+    #[serde(rename = "outerHTML")]
+    pub outer_html: String,
+}
+
+impl Method for GetOuterHtml {
+    const NAME: &'static str = "DOM.getOuterHTML";
+    type Returns = GetOuterHtmlReturns;
+}
+
+/// Sets an attribute value on the element with the given id.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAttributeValue {
+    pub node_id: NodeId,
+    pub name: String,
+    pub value: String,
+}
+
+impl Method for SetAttributeValue {
+    const NAME: &'static str = "DOM.setAttributeValue";
+    type Returns = Empty;
+}
+
+/// Fired when the document has been totally updated. Previously retrieved node
+/// ids are no longer valid.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocumentUpdated {}
+
+impl Event for DocumentUpdated {
+    const NAME: &'static str = "DOM.documentUpdated";
+}
+
+/// Fired when the backend wants to provide the client with missing DOM
+/// structure.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetChildNodes {
+    pub parent_id: NodeId,
+    pub nodes: Vec<Node>,
+}
+
+impl Event for SetChildNodes {
+    const NAME: &'static str = "DOM.setChildNodes";
+}
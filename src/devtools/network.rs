@@ -0,0 +1,157 @@
+//! Methods and events of the `Network` domain.
+
+use super::{Empty, Event, Method};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// HTTP request data.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Request {
+    pub url: String,
+    pub method: String,
+    pub headers: HashMap<String, Value>,
+    pub post_data: Option<String>,
+    pub has_post_data: Option<bool>,
+}
+
+/// HTTP response data.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+    pub url: String,
+    pub status: i64,
+    pub status_text: String,
+    pub headers: HashMap<String, Value>,
+    pub mime_type: String,
+    pub remote_ip_address: Option<String>,
+    pub from_disk_cache: Option<bool>,
+    pub encoded_data_length: f64,
+}
+
+/// Specifies whether to always send extra HTTP headers with the requests from
+/// this page.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExtraHttpHeaders {
+    pub headers: HashMap<String, String>,
+}
+
+impl Method for SetExtraHttpHeaders {
+    const NAME: &'static str = "Network.setExtraHTTPHeaders";
+    type Returns = Empty;
+}
+
+/// Allows overriding the user agent with the given string.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetUserAgentOverride {
+    pub user_agent: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept_language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub platform: Option<String>,
+}
+
+impl Method for SetUserAgentOverride {
+    const NAME: &'static str = "Network.setUserAgentOverride";
+    type Returns = Empty;
+}
+
+/// Toggles ignoring the cache for each request.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCacheDisabled {
+    pub cache_disabled: bool,
+}
+
+impl Method for SetCacheDisabled {
+    const NAME: &'static str = "Network.setCacheDisabled";
+    type Returns = Empty;
+}
+
+/// Returns the content of the response body for the given request.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetResponseBody {
+    pub request_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetResponseBodyReturns {
+    pub body: String,
+    /// True if the body was sent base64-encoded.
+    pub base64_encoded: bool,
+}
+
+impl Method for GetResponseBody {
+    const NAME: &'static str = "Network.getResponseBody";
+    type Returns = GetResponseBodyReturns;
+}
+
+/// Fired when the page is about to send an HTTP request.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestWillBeSent {
+    pub request_id: String,
+    pub loader_id: String,
+    #[serde(rename = "documentURL")]
+    pub document_url: String,
+    pub request: Request,
+    pub timestamp: f64,
+    #[serde(rename = "type")]
+    pub resource_type: Option<String>,
+    pub frame_id: Option<String>,
+}
+
+impl Event for RequestWillBeSent {
+    const NAME: &'static str = "Network.requestWillBeSent";
+}
+
+/// Fired when an HTTP response is available.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResponseReceived {
+    pub request_id: String,
+    pub loader_id: String,
+    pub timestamp: f64,
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    pub response: Response,
+    pub frame_id: Option<String>,
+}
+
+impl Event for ResponseReceived {
+    const NAME: &'static str = "Network.responseReceived";
+}
+
+/// Fired when an HTTP request has finished loading.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadingFinished {
+    pub request_id: String,
+    pub timestamp: f64,
+    pub encoded_data_length: f64,
+}
+
+impl Event for LoadingFinished {
+    const NAME: &'static str = "Network.loadingFinished";
+}
+
+/// Fired when an HTTP request has failed to load.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadingFailed {
+    pub request_id: String,
+    pub timestamp: f64,
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    pub error_text: String,
+    pub canceled: Option<bool>,
+}
+
+impl Event for LoadingFailed {
+    const NAME: &'static str = "Network.loadingFailed";
+}
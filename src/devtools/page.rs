@@ -0,0 +1,149 @@
+//! Methods and events of the `Page` domain.
+
+use super::{Empty, Event, Method};
+use serde::{Deserialize, Serialize};
+
+/// Information about a frame on the page.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Frame {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub loader_id: String,
+    pub name: Option<String>,
+    pub url: String,
+    pub security_origin: String,
+    pub mime_type: String,
+}
+
+/// Navigates the current page to the given URL.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Navigate {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referrer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_id: Option<String>,
+}
+
+impl Navigate {
+    pub fn new(url: &str) -> Navigate {
+        Navigate {
+            url: url.to_owned(),
+            referrer: None,
+            transition_type: None,
+            frame_id: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NavigateReturns {
+    /// Frame id that has navigated (or failed to navigate).
+    pub frame_id: String,
+    /// Loader identifier. This is omitted for same-document navigations.
+    pub loader_id: Option<String>,
+    /// User friendly error message, present if and only if navigation has failed.
+    pub error_text: Option<String>,
+}
+
+impl Method for Navigate {
+    const NAME: &'static str = "Page.navigate";
+    type Returns = NavigateReturns;
+}
+
+/// Reloads the given page, optionally ignoring the cache.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_cache: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script_to_evaluate_on_load: Option<String>,
+}
+
+impl Method for Reload {
+    const NAME: &'static str = "Page.reload";
+    type Returns = Empty;
+}
+
+/// Captures a screenshot of the page.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureScreenshot {
+    /// Image compression format, either `jpeg` or `png` (the default).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Compression quality from 0 to 100 (jpeg only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_surface: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureScreenshotReturns {
+    /// Base64-encoded image data.
+    pub data: String,
+}
+
+impl Method for CaptureScreenshot {
+    const NAME: &'static str = "Page.captureScreenshot";
+    type Returns = CaptureScreenshotReturns;
+}
+
+/// Evaluates the given script in every frame upon creation, before loading the
+/// frame's scripts.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddScriptToEvaluateOnNewDocument {
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddScriptToEvaluateOnNewDocumentReturns {
+    /// Identifier of the added script.
+    pub identifier: String,
+}
+
+impl Method for AddScriptToEvaluateOnNewDocument {
+    const NAME: &'static str = "Page.addScriptToEvaluateOnNewDocument";
+    type Returns = AddScriptToEvaluateOnNewDocumentReturns;
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadEventFired {
+    pub timestamp: f64,
+}
+
+impl Event for LoadEventFired {
+    const NAME: &'static str = "Page.loadEventFired";
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomContentEventFired {
+    pub timestamp: f64,
+}
+
+impl Event for DomContentEventFired {
+    const NAME: &'static str = "Page.domContentEventFired";
+}
+
+/// Fired once navigation of the frame has completed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameNavigated {
+    pub frame: Frame,
+}
+
+impl Event for FrameNavigated {
+    const NAME: &'static str = "Page.frameNavigated";
+}
@@ -0,0 +1,138 @@
+//! Methods and events of the `Runtime` domain.
+
+use super::{Empty, Event, Method};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Mirror object referencing the original JavaScript object.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteObject {
+    /// Object type, for example `object`, `string` or `undefined`.
+    #[serde(rename = "type")]
+    pub object_type: String,
+    pub subtype: Option<String>,
+    pub class_name: Option<String>,
+    /// Remote object value in case of primitive values or JSON values (if it was
+    /// requested).
+    pub value: Option<Value>,
+    pub unserializable_value: Option<String>,
+    pub description: Option<String>,
+    /// Unique object identifier (for non-primitive values).
+    pub object_id: Option<String>,
+}
+
+/// Detailed information about an exception (or error) that was thrown during
+/// script compilation or execution.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExceptionDetails {
+    pub exception_id: i64,
+    pub text: String,
+    pub line_number: i64,
+    pub column_number: i64,
+    pub script_id: Option<String>,
+    pub url: Option<String>,
+    pub exception: Option<RemoteObject>,
+}
+
+/// Description of an isolated world.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionContextDescription {
+    pub id: i64,
+    pub origin: String,
+    pub name: String,
+}
+
+/// Evaluates an expression on the global object.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Evaluate {
+    pub expression: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object_group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub silent: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_by_value: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub await_promise: Option<bool>,
+}
+
+impl Evaluate {
+    pub fn new(expression: &str) -> Evaluate {
+        Evaluate {
+            expression: expression.to_owned(),
+            object_group: None,
+            silent: None,
+            context_id: None,
+            return_by_value: None,
+            await_promise: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateReturns {
+    pub result: RemoteObject,
+    pub exception_details: Option<ExceptionDetails>,
+}
+
+impl Method for Evaluate {
+    const NAME: &'static str = "Runtime.evaluate";
+    type Returns = EvaluateReturns;
+}
+
+/// Releases the remote object with the given id.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseObject {
+    pub object_id: String,
+}
+
+impl Method for ReleaseObject {
+    const NAME: &'static str = "Runtime.releaseObject";
+    type Returns = Empty;
+}
+
+/// Issued when a console API was called, for example `console.log`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsoleApiCalled {
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub args: Vec<RemoteObject>,
+    pub execution_context_id: i64,
+    pub timestamp: f64,
+}
+
+impl Event for ConsoleApiCalled {
+    const NAME: &'static str = "Runtime.consoleAPICalled";
+}
+
+/// Issued when an exception was thrown and is unhandled.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExceptionThrown {
+    pub timestamp: f64,
+    pub exception_details: ExceptionDetails,
+}
+
+impl Event for ExceptionThrown {
+    const NAME: &'static str = "Runtime.exceptionThrown";
+}
+
+/// Issued when a new execution context is created.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionContextCreated {
+    pub context: ExecutionContextDescription,
+}
+
+impl Event for ExecutionContextCreated {
+    const NAME: &'static str = "Runtime.executionContextCreated";
+}
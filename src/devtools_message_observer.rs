@@ -28,7 +28,7 @@ pub trait DevToolsMessageObserverCallbacks: 'static + Send {
     // optionally a `params` (dictionary) value. See the DevTools protocol
     // documentation at https://chromedevtools.github.io/devtools-protocol/ for
     // details of supported function calls and the expected `result` or `params`
-    // dictionary contents. JSON dictionaries can be parsed using the CefParseJSON
+    // dictionary contents. JSON dictionaries can be parsed using the `values::parse_json`
     // function if desired, however be aware of performance considerations when
     // parsing large messages (some of which may exceed 1MB in size).
     fn on_dev_tools_message(
@@ -95,7 +95,7 @@ pub trait DevToolsMessageObserverCallbacks: 'static + Send {
 struct DevToolsMessageObserverWrapper(SendProtectorMut<Box<dyn DevToolsMessageObserverCallbacks>>);
 
 impl DevToolsMessageObserver {
-    fn new(callbacks: impl DevToolsMessageObserverCallbacks) -> DevToolsMessageObserver {
+    pub fn new(callbacks: impl DevToolsMessageObserverCallbacks) -> DevToolsMessageObserver {
         unsafe{ DevToolsMessageObserver::from_ptr_unchecked(DevToolsMessageObserverWrapper(SendProtectorMut::new(Box::new(callbacks))).wrap().into_raw()) }
     }
 }
@@ -0,0 +1,305 @@
+//! Plumbing for [Frame::evaluate], which runs JavaScript in the render process
+//! and reports the result back to the browser process.
+//!
+//! The browser process sends a [ProcessMessage] with a request id to the
+//! renderer. The crate's [RenderProcessHandler](crate::render_process_handler::RenderProcessHandler)
+//! wrapper intercepts it before any user callbacks are called, evaluates the code
+//! with [V8Context::eval](crate::v8context::V8Context::eval) and answers with the
+//! converted result or exception. The crate's [Client](crate::client::Client)
+//! wrapper intercepts the answer and completes the matching future.
+
+use crate::{
+    browser::Browser,
+    frame::Frame,
+    process::ProcessMessage,
+    task::{TaskRunner, ThreadId},
+    v8context::{V8Exception, V8Value},
+    values::{DictionaryValue, ListValue, StoredValue},
+};
+use futures::channel::oneshot;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicI32, Ordering},
+    task::{Context, Poll},
+    time::{Duration, UNIX_EPOCH},
+};
+
+const REQUEST_MESSAGE: &str = "cef-rs.evaluate";
+const RESPONSE_MESSAGE: &str = "cef-rs.evaluate.response";
+
+/// How long [Frame::evaluate] waits for the renderer before giving up.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Objects and arrays nested deeper than this are converted to
+/// [StoredValue::Null], which also breaks reference cycles.
+const MAX_DEPTH: usize = 32;
+
+/// A JavaScript exception thrown while evaluating code with [Frame::evaluate].
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsException {
+    /// The exception message.
+    pub message: String,
+    /// The line of source code that the exception occurred within.
+    pub source_line: String,
+    /// The resource name for the script from where the function causing the
+    /// error originates.
+    pub script_resource_name: String,
+    /// The 1-based number of the line where the error occurred or 0 if the
+    /// line number is unknown.
+    pub line_number: i32,
+    /// The index within the line of the first character where the error
+    /// occurred.
+    pub start_column: i32,
+    /// The index within the line of the last character where the error
+    /// occurred.
+    pub end_column: i32,
+}
+
+impl fmt::Display for JsException {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}:{}:{})", self.message, self.script_resource_name, self.line_number, self.start_column)
+    }
+}
+
+impl std::error::Error for JsException {}
+
+/// Errors that can occur in [Frame::evaluate].
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvaluateError {
+    /// The code threw an exception.
+    Exception(JsException),
+    /// The renderer didn't answer in time.
+    Timeout,
+    /// The render process terminated or the browser was closed before the
+    /// renderer answered.
+    RendererGone,
+    /// The renderer answered with a message that couldn't be decoded.
+    InvalidResponse,
+}
+
+impl fmt::Display for EvaluateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvaluateError::Exception(exception) => write!(f, "JavaScript exception: {}", exception),
+            EvaluateError::Timeout => write!(f, "timed out waiting for the renderer"),
+            EvaluateError::RendererGone => write!(f, "the renderer went away before answering"),
+            EvaluateError::InvalidResponse => write!(f, "invalid response from the renderer"),
+        }
+    }
+}
+
+impl std::error::Error for EvaluateError {}
+
+struct PendingEvaluation {
+    browser_id: i32,
+    sender: oneshot::Sender<Result<StoredValue, EvaluateError>>,
+}
+
+lazy_static::lazy_static! {
+    static ref PENDING: Mutex<HashMap<i32, PendingEvaluation>> = Mutex::new(HashMap::new());
+}
+
+static NEXT_REQUEST_ID: AtomicI32 = AtomicI32::new(1);
+
+/// A pending [Frame::evaluate] call. Dropping it stops waiting for the result.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Evaluation {
+    request_id: i32,
+    receiver: oneshot::Receiver<Result<StoredValue, EvaluateError>>,
+}
+
+impl Future for Evaluation {
+    type Output = Result<StoredValue, EvaluateError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver)
+            .poll(cx)
+            .map(|result| result.unwrap_or(Err(EvaluateError::RendererGone)))
+    }
+}
+
+impl Drop for Evaluation {
+    fn drop(&mut self) {
+        PENDING.lock().remove(&self.request_id);
+    }
+}
+
+pub(crate) fn evaluate(frame: &Frame, code: &str, script_url: &str, timeout: Duration) -> Evaluation {
+    let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    let (sender, receiver) = oneshot::channel();
+    PENDING.lock().insert(request_id, PendingEvaluation {
+        browser_id: frame.get_browser().get_identifier(),
+        sender,
+    });
+
+    let message = ProcessMessage::new(REQUEST_MESSAGE);
+    let args = message.get_argument_list();
+    args.set_int(0, request_id);
+    args.set_string(1, code);
+    args.set_string(2, script_url);
+    frame.send_process_message(message);
+
+    TaskRunner::post_delayed_task_on(ThreadId::UI, move || {
+        complete(request_id, Err(EvaluateError::Timeout));
+    }, timeout.as_millis() as i64);
+
+    Evaluation {
+        request_id,
+        receiver,
+    }
+}
+
+fn complete(request_id: i32, result: Result<StoredValue, EvaluateError>) {
+    let pending = PENDING.lock().remove(&request_id);
+    if let Some(pending) = pending {
+        let _ = pending.sender.send(result);
+    }
+}
+
+/// Fails all evaluations waiting for an answer from `browser`. Called when its
+/// render process terminates or the browser closes.
+pub(crate) fn fail_browser(browser: &Browser) {
+    let browser_id = browser.get_identifier();
+    let failed = {
+        let mut pending = PENDING.lock();
+        let ids = pending.iter()
+            .filter(|(_, evaluation)| evaluation.browser_id == browser_id)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        ids.into_iter().filter_map(|id| pending.remove(&id)).collect::<Vec<_>>()
+    };
+    for evaluation in failed {
+        let _ = evaluation.sender.send(Err(EvaluateError::RendererGone));
+    }
+}
+
+/// Handles evaluation responses in the browser process. Returns true if
+/// `message` was one.
+pub(crate) fn on_browser_process_message(message: &ProcessMessage) -> bool {
+    if message.get_name().as_ref().map(String::as_str) != Some(RESPONSE_MESSAGE) {
+        return false;
+    }
+    let args = message.get_argument_list();
+    if let Some(request_id) = args.get_int(0) {
+        let result = match args.get_bool(1) {
+            Some(true) => args.get(2).ok_or(EvaluateError::InvalidResponse),
+            Some(false) => Err(args.get_dictionary(2)
+                .map(|exception| EvaluateError::Exception(exception_from_dictionary(&exception)))
+                .unwrap_or(EvaluateError::InvalidResponse)),
+            None => Err(EvaluateError::InvalidResponse),
+        };
+        complete(request_id, result);
+    }
+    true
+}
+
+/// Handles evaluation requests in the render process. Returns true if `message`
+/// was one.
+pub(crate) fn on_render_process_message(frame: &Frame, message: &ProcessMessage) -> bool {
+    if message.get_name().as_ref().map(String::as_str) != Some(REQUEST_MESSAGE) {
+        return false;
+    }
+    let args = message.get_argument_list();
+    let request_id = match args.get_int(0) {
+        Some(request_id) => request_id,
+        None => return true,
+    };
+    let code = args.get_string(1).unwrap_or_default();
+    let script_url = args.get_string(2).unwrap_or_default();
+
+    let response = ProcessMessage::new(RESPONSE_MESSAGE);
+    let response_args = response.get_argument_list();
+    response_args.set_int(0, request_id);
+    let context = frame.get_v8context();
+    match context.eval(&code, &script_url, 1) {
+        Ok(value) => {
+            let value = context.execute_in_context(|| v8_to_stored_value(&value, 0))
+                .unwrap_or(StoredValue::Null);
+            response_args.set_bool(1, true);
+            response_args.set_value_inner(2, TryFrom::try_from(value).unwrap());
+        }
+        Err(exception) => {
+            response_args.set_bool(1, false);
+            response_args.set_dictionary(2, exception_to_dictionary(&exception));
+        }
+    }
+    frame.send_process_message(response);
+    true
+}
+
+fn exception_to_dictionary(exception: &V8Exception) -> DictionaryValue {
+    let dictionary = DictionaryValue::new();
+    dictionary.insert_string("message", &exception.get_message());
+    dictionary.insert_string("source_line", &exception.get_source_line());
+    dictionary.insert_string("script_resource_name", &exception.get_script_resource_name());
+    dictionary.insert_int("line_number", exception.get_line_number());
+    dictionary.insert_int("start_column", exception.get_start_column());
+    dictionary.insert_int("end_column", exception.get_end_column());
+    dictionary
+}
+
+fn exception_from_dictionary(dictionary: &DictionaryValue) -> JsException {
+    JsException {
+        message: dictionary.get_string("message"),
+        source_line: dictionary.get_string("source_line"),
+        script_resource_name: dictionary.get_string("script_resource_name"),
+        line_number: dictionary.get_int("line_number"),
+        start_column: dictionary.get_int("start_column"),
+        end_column: dictionary.get_int("end_column"),
+    }
+}
+
+/// Converts a V8 value to a value that can be sent across processes. Functions
+/// and `undefined` become null, dates become milliseconds since the epoch.
+/// Must be called with the value's context entered.
+pub(crate) fn v8_to_stored_value(value: &V8Value, depth: usize) -> StoredValue {
+    if value.is_undefined() || value.is_null() || value.is_function() {
+        StoredValue::Null
+    } else if let Some(value) = value.get_bool_value() {
+        StoredValue::Bool(value)
+    } else if let Some(value) = value.get_int_value() {
+        StoredValue::Int(value)
+    } else if let Some(value) = value.get_uint_value() {
+        i32::try_from(value)
+            .map(StoredValue::Int)
+            .unwrap_or(StoredValue::Double(value as f64))
+    } else if let Some(value) = value.get_double_value() {
+        StoredValue::Double(value)
+    } else if let Some(value) = value.get_string_value() {
+        StoredValue::String(value)
+    } else if let Some(value) = value.get_date_value() {
+        let millis = match value.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_secs_f64() * 1000.0,
+            Err(error) => -error.duration().as_secs_f64() * 1000.0,
+        };
+        StoredValue::Double(millis)
+    } else if depth >= MAX_DEPTH {
+        StoredValue::Null
+    } else if value.is_array() {
+        let list = ListValue::new();
+        for index in 0..value.get_array_length() {
+            let item = value.get_value_byindex(index)
+                .map(|item| v8_to_stored_value(&item, depth + 1))
+                .unwrap_or(StoredValue::Null);
+            list.set_value_inner(index as usize, TryFrom::try_from(item).unwrap());
+        }
+        StoredValue::List(list)
+    } else if value.is_object() {
+        let dictionary = DictionaryValue::new();
+        for key in value.get_keys().unwrap_or_default() {
+            if let Some(item) = value.get_value_bykey(&key) {
+                if !item.is_function() {
+                    dictionary.insert(&key, v8_to_stored_value(&item, depth + 1));
+                }
+            }
+        }
+        StoredValue::Dictionary(dictionary)
+    } else {
+        StoredValue::Null
+    }
+}
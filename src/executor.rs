@@ -0,0 +1,193 @@
+//! A minimal executor that drives Rust futures on CEF's own threads.
+//!
+//! Futures are polled inside tasks posted to a [TaskRunner]. Whenever a future's
+//! [Waker](std::task::Waker) is woken, a new poll task is posted to the same runner, so the future
+//! always makes progress on the thread it was spawned on. This makes it safe to
+//! `.await` across CEF callbacks while keeping the thread affinity most CEF APIs
+//! require.
+//!
+//! This module also provides [CallbackFuture], which the `async` counterparts of
+//! CEF's one-shot callback and visitor APIs return.
+
+use crate::task::{TaskRunner, ThreadId};
+use futures::{
+    channel::oneshot,
+    future::FutureExt,
+    task::{waker_ref, ArcWake},
+};
+use parking_lot::Mutex;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+pub use futures::channel::oneshot::Canceled;
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Spawns a future on the task runner of the current thread.
+///
+/// # Panics
+///
+/// Panics if the current thread is not a CEF thread.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let runner = TaskRunner::get_for_current_thread()
+        .expect("executor::spawn must be called on a CEF thread");
+    runner.spawn(future)
+}
+
+/// Spawns a future on the specified CEF thread. Returns None if no task runner
+/// is available for that thread in the current process.
+pub fn spawn_on<F>(thread_id: ThreadId, future: F) -> Option<JoinHandle<F::Output>>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    TaskRunner::get_for_thread(thread_id).map(|runner| runner.spawn(future))
+}
+
+/// Runs a blocking closure on [ThreadId::FileBackground] and returns a handle that
+/// resolves with its result. Use this to keep long-running work off the UI and IO
+/// threads.
+pub fn spawn_blocking<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    TaskRunner::post_task_on(ThreadId::FileBackground, move || {
+        let _ = sender.send(f());
+    });
+    JoinHandle(receiver)
+}
+
+impl TaskRunner {
+    /// Spawns a future on the thread associated with this task runner. The future
+    /// is first polled asynchronously, and every time it is woken a new poll is
+    /// posted to this task runner.
+    ///
+    /// If the task runner stops accepting tasks before the future completes, the
+    /// returned [JoinHandle] resolves with [Canceled].
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let future = future.map(move |output| {
+            let _ = sender.send(output);
+        });
+        let task = Arc::new(SpawnedTask {
+            future: Mutex::new(Some(Box::pin(future))),
+            runner: self.clone(),
+            scheduled: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
+        });
+        task.schedule();
+        JoinHandle(receiver)
+    }
+}
+
+/// A handle to a future spawned with [spawn], [spawn_on] or [TaskRunner::spawn].
+///
+/// Awaiting the handle yields the future's output, or [Canceled] if the future was
+/// dropped before it completed. Dropping the handle detaches the future; it keeps
+/// running to completion.
+pub struct JoinHandle<T>(oneshot::Receiver<T>);
+
+impl<T> JoinHandle<T> {
+    /// Returns the output if the future has already completed, without blocking.
+    pub fn try_get(&mut self) -> Result<Option<T>, Canceled> {
+        self.0.try_recv()
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, Canceled>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+/// A future that resolves once CEF invokes a one-shot callback or finishes running
+/// a visitor.
+///
+/// Resolves with [Canceled] if CEF releases the callback without ever calling it,
+/// for example because the operation could not be started. Dropping the future
+/// cancels it: the result is discarded, and visitors stop at the next item.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct CallbackFuture<T>(oneshot::Receiver<T>);
+
+impl<T> CallbackFuture<T> {
+    /// Creates a future together with the sender half that the callback
+    /// wrapper should complete.
+    pub(crate) fn new() -> (oneshot::Sender<T>, CallbackFuture<T>) {
+        let (sender, receiver) = oneshot::channel();
+        (sender, CallbackFuture(receiver))
+    }
+}
+
+impl<T> Future for CallbackFuture<T> {
+    type Output = Result<T, Canceled>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+struct SpawnedTask {
+    future: Mutex<Option<BoxFuture>>,
+    runner: TaskRunner,
+    /// Set while a poll task is queued on the runner, so that multiple wakeups
+    /// between two polls only post a single task.
+    scheduled: AtomicBool,
+    /// Set once the runner refused a poll task. The future is dropped instead of
+    /// being polled again.
+    closed: AtomicBool,
+}
+
+impl SpawnedTask {
+    fn schedule(self: &Arc<Self>) {
+        if self.scheduled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let task = self.clone();
+        if !self.runner.post_task(move || task.run()) {
+            // The runner is shutting down. Drop the future now, unless it is
+            // currently being polled, in which case `run` will drop it.
+            self.closed.store(true, Ordering::Release);
+            if let Some(mut future) = self.future.try_lock() {
+                future.take();
+            }
+        }
+    }
+
+    fn run(self: Arc<Self>) {
+        self.scheduled.store(false, Ordering::Release);
+        let mut slot = self.future.lock();
+        if let Some(mut future) = slot.take() {
+            let waker = waker_ref(&self);
+            let mut cx = Context::from_waker(&*waker);
+            if future.as_mut().poll(&mut cx).is_pending() && !self.closed.load(Ordering::Acquire) {
+                *slot = Some(future);
+            }
+        }
+    }
+}
+
+impl ArcWake for SpawnedTask {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.schedule();
+    }
+}
+
@@ -13,6 +13,8 @@ use cef_sys::cef_browser_settings_t;
 use std::os::raw::c_int;
 use crate::client::Client;
 use cef_sys::{cef_extension_t, cef_extension_handler_t, cef_errorcode_t};
+use futures::channel::oneshot;
+use parking_lot::Mutex;
 use std::{
     collections::HashMap,
     path::PathBuf,
@@ -236,6 +238,79 @@ pub trait ExtensionHandlerCallbacks: 'static + Send {
     ) -> bool;
 }
 
+/// Extension handler used by [RequestContext::load_extension_async]. It reports the
+/// load result through a channel and otherwise mirrors CEF's default behavior.
+pub(crate) struct LoadExtensionHandler(Mutex<Option<oneshot::Sender<Result<Extension, ErrorCode>>>>);
+
+impl LoadExtensionHandler {
+    pub(crate) fn new(sender: oneshot::Sender<Result<Extension, ErrorCode>>) -> LoadExtensionHandler {
+        LoadExtensionHandler(Mutex::new(Some(sender)))
+    }
+}
+
+impl ExtensionHandlerCallbacks for LoadExtensionHandler {
+    fn on_extension_load_failed(&self, result: ErrorCode) {
+        if let Some(sender) = self.0.lock().take() {
+            let _ = sender.send(Err(result));
+        }
+    }
+    fn on_extension_loaded(&self, extension: Extension) {
+        if let Some(sender) = self.0.lock().take() {
+            let _ = sender.send(Ok(extension));
+        }
+    }
+    fn on_extension_unloaded(&self, extension: Extension) {}
+    fn on_before_background_browser(
+        &self,
+        extension: Extension,
+        url: &str,
+        client: &mut Client,
+        settings: &mut BrowserSettings
+    ) -> bool {
+        false
+    }
+    fn on_before_browser(
+        &self,
+        extension: Extension,
+        browser: Browser,
+        active_browser: Browser,
+        index: usize,
+        url: &str,
+        active: bool,
+        window_info: &mut WindowInfo,
+        client: &mut Client,
+        settings: &mut BrowserSettings,
+    ) -> bool {
+        false
+    }
+    fn get_active_browser(
+        &self,
+        extension: Extension,
+        browser: Browser,
+        include_incognito: bool
+    ) -> Browser {
+        browser
+    }
+    fn can_access_browser(
+        &self,
+        extension: Extension,
+        browser: Browser,
+        include_incognito: bool,
+        target_browser: Browser,
+    ) -> bool {
+        true
+    }
+    fn get_extension_resource(
+        &self,
+        extension: Extension,
+        browser: Browser,
+        file: &str,
+        callback: GetExtensionResourceCallback,
+    ) -> bool {
+        false
+    }
+}
+
 struct ExtensionHandlerWrapper {
     delegate: SendProtector<Box<dyn ExtensionHandlerCallbacks>>
 }
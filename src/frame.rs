@@ -6,7 +6,10 @@ use crate::{
     url_request::{URLRequest, URLRequestClient},
     v8context::V8Context,
     process::{ProcessId, ProcessMessage},
+    executor::CallbackFuture,
+    evaluate::Evaluation,
 };
+use std::time::Duration;
 use cef_sys::{cef_frame_t, cef_string_userfree_utf16_free};
 
 ref_counted_ptr! {
@@ -109,6 +112,26 @@ impl Frame {
             }
         }
     }
+    /// Retrieve this frame's HTML source. Async version of [Frame::get_source].
+    pub fn source(&self) -> CallbackFuture<String> {
+        let (sender, future) = CallbackFuture::new();
+        self.get_source(Self::string_visitor_once(sender));
+        future
+    }
+    /// Retrieve this frame's display text. Async version of [Frame::get_text].
+    pub fn text(&self) -> CallbackFuture<String> {
+        let (sender, future) = CallbackFuture::new();
+        self.get_text(Self::string_visitor_once(sender));
+        future
+    }
+    fn string_visitor_once(sender: futures::channel::oneshot::Sender<String>) -> StringVisitor {
+        let mut sender = Some(sender);
+        StringVisitor::new(move |string: &str| {
+            if let Some(sender) = sender.take() {
+                let _ = sender.send(string.to_owned());
+            }
+        })
+    }
     /// Load the request represented by the |request| object.
     pub fn load_request(&self, request: Request) {
         if let Some(load_request) = self.0.load_request {
@@ -142,6 +165,20 @@ impl Frame {
             }
         }
     }
+    /// Evaluate a string of JavaScript code in this frame and return the value of
+    /// the last expression. The code runs in the render process via
+    /// [V8Context::eval]; the result is converted to a [StoredValue](crate::values::StoredValue),
+    /// turning functions and `undefined` into null. Fails with a timeout if the
+    /// renderer doesn't answer within [DEFAULT_TIMEOUT](crate::evaluate::DEFAULT_TIMEOUT).
+    /// This function can only be called from the browser process.
+    pub fn evaluate(&self, code: &str) -> Evaluation {
+        self.evaluate_with_timeout(code, "", crate::evaluate::DEFAULT_TIMEOUT)
+    }
+    /// Like [Frame::evaluate], with the `script_url` used for error reporting and
+    /// a custom `timeout`.
+    pub fn evaluate_with_timeout(&self, code: &str, script_url: &str, timeout: Duration) -> Evaluation {
+        crate::evaluate::evaluate(self, code, script_url, timeout)
+    }
     /// Returns true if this is the main (top-level) frame.
     pub fn is_main(&self) -> bool {
         if let Some(is_main) = self.0.is_main {
@@ -283,4 +320,11 @@ impl Frame {
             }
         }
     }
+    /// Encodes `message` and sends it to the other process, where it is passed to
+    /// the handler registered for `M` with [register_handler](crate::message::register_handler).
+    #[cfg(feature = "typed-messages")]
+    pub fn send_typed<M: crate::message::CefMessage>(&self, message: M) -> Result<(), crate::values::Error> {
+        self.send_process_message(crate::message::encode(&message)?);
+        Ok(())
+    }
 }
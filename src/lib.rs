@@ -20,12 +20,16 @@ pub mod browser_process_handler;
 pub mod browser;
 pub mod browser_host;
 pub mod frame;
+pub mod evaluate;
+pub mod message_router;
 pub mod load_handler;
 pub mod registration;
 pub mod render_process_handler;
 pub mod dom;
 pub mod v8context;
 pub mod process;
+#[cfg(feature = "typed-messages")]
+pub mod message;
 pub mod request;
 pub mod response;
 pub mod url_request;
@@ -33,6 +37,8 @@ pub mod request_context;
 pub mod web_plugin;
 pub mod cookie;
 pub mod devtools_message_observer;
+#[cfg(feature = "devtools")]
+pub mod devtools;
 pub mod callback;
 pub mod resource_request_handler;
 pub mod client;
@@ -55,10 +61,16 @@ pub mod window;
 pub mod x509_certificate;
 pub mod ime;
 pub mod navigation;
+pub mod parser;
 pub mod extension;
 pub mod stream;
+pub mod xml_reader;
+pub mod zip_reader;
 pub mod ssl;
+pub mod server;
 pub mod task;
+pub mod thread;
+pub mod executor;
 pub mod logging;
 mod send_protector;
 #[cfg(target_os = "macos")] mod framework_loader_macos;
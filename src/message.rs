@@ -0,0 +1,112 @@
+//! Typed process messages.
+//!
+//! A type deriving [CefMessage] (together with serde's `Serialize` and
+//! `Deserialize`) can be sent with [Frame::send_typed]. The payload is encoded
+//! with [values::to_value](crate::values::to_value) into the first argument of a
+//! [ProcessMessage] named [CefMessage::NAME].
+//!
+//! The receiving process dispatches messages by name to the handler registered
+//! with [register_handler], before any
+//! [ClientCallbacks::on_process_message_received](crate::client::ClientCallbacks::on_process_message_received)
+//! or [RenderProcessHandlerCallbacks::on_process_message_received](crate::render_process_handler::RenderProcessHandlerCallbacks::on_process_message_received)
+//! callbacks are called. Messages that fail to decode are logged and dropped;
+//! messages that neither a typed handler nor the callbacks handle are logged.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize, CefMessage)]
+//! struct Navigate {
+//!     url: String,
+//! }
+//!
+//! // In the receiving process:
+//! message::register_handler(|browser, frame, message: Navigate| frame.load_url(&message.url));
+//! // In the sending process:
+//! frame.send_typed(Navigate { url: "https://example.com".to_owned() })?;
+//! ```
+
+use crate::{
+    browser::Browser,
+    frame::Frame,
+    process::ProcessMessage,
+    values::{self, StoredValue, Value},
+};
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, convert::TryFrom, sync::Arc};
+
+pub use cef_derive::CefMessage;
+
+/// A message that can be sent between processes with [Frame::send_typed]. Use
+/// `#[derive(CefMessage)]` to implement it.
+pub trait CefMessage: Serialize + DeserializeOwned + Send + 'static {
+    /// The name of the [ProcessMessage] carrying this type. It must be unique
+    /// among all message types handled by a process.
+    const NAME: &'static str;
+}
+
+type Handler = Arc<dyn Fn(Browser, Frame, StoredValue) + Send + Sync>;
+
+lazy_static::lazy_static! {
+    static ref HANDLERS: Mutex<HashMap<&'static str, Handler>> = Mutex::new(HashMap::new());
+}
+
+/// Registers `handler` for messages of type `M` received by the current process,
+/// replacing any handler previously registered for `M`. The handler is called
+/// on the thread that receives process messages: the UI thread in the browser
+/// process and the main thread in the render process.
+pub fn register_handler<M, F>(handler: F)
+where
+    M: CefMessage,
+    F: Fn(Browser, Frame, M) + Send + Sync + 'static,
+{
+    let handler: Handler = Arc::new(move |browser, frame, payload| {
+        match values::from_value::<M>(payload) {
+            Ok(message) => handler(browser, frame, message),
+            Err(error) => log::error!("failed to decode process message {}: {}", M::NAME, error),
+        }
+    });
+    HANDLERS.lock().insert(M::NAME, handler);
+}
+
+/// Removes the handler registered for messages of type `M`. Returns true if
+/// there was one.
+pub fn unregister_handler<M: CefMessage>() -> bool {
+    HANDLERS.lock().remove(M::NAME).is_some()
+}
+
+/// Encodes `message` into a [ProcessMessage].
+pub(crate) fn encode<M: CefMessage>(message: &M) -> Result<ProcessMessage, values::Error> {
+    let payload = values::to_value(message)?;
+    let process_message = ProcessMessage::new(M::NAME);
+    process_message
+        .get_argument_list()
+        .set_value_inner(0, Value::try_from(payload).map_err(|error| values::Error::Message(error.to_owned()))?);
+    Ok(process_message)
+}
+
+/// Calls the handler registered for `message`'s name. Returns true if there
+/// was one.
+pub(crate) fn dispatch(browser: &Browser, frame: &Frame, message: &ProcessMessage) -> bool {
+    let name = match message.get_name() {
+        Some(name) => name,
+        None => return false,
+    };
+    let handler = HANDLERS.lock().get(name.as_str()).cloned();
+    match handler {
+        Some(handler) => {
+            let payload = message.get_argument_list().get(0).unwrap_or(StoredValue::Null);
+            handler(browser.clone(), frame.clone(), payload);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Logs a message that wasn't handled by a typed handler or the user's
+/// callbacks.
+pub(crate) fn log_unhandled(message: &ProcessMessage) {
+    log::warn!(
+        "unhandled process message {}",
+        message.get_name().as_ref().map(String::as_str).unwrap_or("<unnamed>")
+    );
+}
@@ -0,0 +1,671 @@
+//! A port of CEF's generic message router, which implements asynchronous
+//! JavaScript queries of the form
+//!
+//! ```js
+//! let requestId = window.cefQuery({
+//!     request: 'my_request',
+//!     persistent: false,
+//!     onSuccess: function(response) {},
+//!     onFailure: function(errorCode, errorMessage) {},
+//! });
+//! window.cefQueryCancel(requestId);
+//! ```
+//!
+//! The router has two halves. Create a [MessageRouterRendererSide] in the render
+//! process before any V8 contexts are created, for example in
+//! [RenderProcessHandlerCallbacks::on_web_kit_initialized](crate::render_process_handler::RenderProcessHandlerCallbacks::on_web_kit_initialized).
+//! It installs the query functions into every new context. Create a
+//! [MessageRouterBrowserSide] in the browser process and add one or more
+//! [MessageRouterHandler]s to answer the queries. Both halves must use the same
+//! [MessageRouterConfig].
+//!
+//! Unlike the C++ version, the routers don't need to be called from your own
+//! handlers. The crate's [Client](crate::client::Client) and
+//! [RenderProcessHandler](crate::render_process_handler::RenderProcessHandler)
+//! wrappers forward process messages and context notifications to all live
+//! routers, and pending queries are canceled automatically when their frame
+//! navigates, their context is released or their browser closes.
+
+use crate::{
+    browser::Browser,
+    frame::Frame,
+    process::ProcessMessage,
+    task::{TaskRunner, ThreadId},
+    v8context::{V8Context, V8PropertyAttribute, V8Value},
+};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI32, AtomicI64, Ordering},
+        Arc, Weak,
+    },
+};
+
+/// Error code passed to `onFailure` when a query is canceled or no handler
+/// accepted it.
+pub const CANCELED_ERROR_CODE: i32 = -1;
+const CANCELED_ERROR_MESSAGE: &str = "The query has been canceled";
+
+/// Configuration shared by both halves of the message router.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageRouterConfig {
+    /// Name of the JavaScript function that starts a query. Defaults to
+    /// `cefQuery`.
+    pub js_query_function: String,
+    /// Name of the JavaScript function that cancels a pending query. Defaults
+    /// to `cefQueryCancel`.
+    pub js_cancel_function: String,
+}
+
+impl Default for MessageRouterConfig {
+    fn default() -> Self {
+        Self {
+            js_query_function: "cefQuery".to_owned(),
+            js_cancel_function: "cefQueryCancel".to_owned(),
+        }
+    }
+}
+
+impl MessageRouterConfig {
+    fn query_message_name(&self) -> String {
+        format!("{}Msg", self.js_query_function)
+    }
+    fn cancel_message_name(&self) -> String {
+        format!("{}Msg", self.js_cancel_function)
+    }
+}
+
+/// Trait used to answer queries in the browser process. All functions are
+/// called on the UI thread.
+pub trait MessageRouterHandler: 'static + Send + Sync {
+    /// Called when the renderer calls the query function. `query_id` uniquely
+    /// identifies the query for the lifetime of the router. Return true to handle
+    /// the query, in which case `callback` must eventually be used to answer it,
+    /// either synchronously or asynchronously. Return false to pass the query to
+    /// the next handler. If no handler accepts the query, it fails with
+    /// [CANCELED_ERROR_CODE].
+    ///
+    /// If `persistent` is true, [Callback::success] may be called any number of
+    /// times, and the query stays pending until [Callback::failure] is called or
+    /// the query is canceled. Otherwise the first response completes the query.
+    fn on_query(
+        &self,
+        browser: Browser,
+        frame: Frame,
+        query_id: i64,
+        request: &str,
+        persistent: bool,
+        callback: Callback,
+    ) -> bool {
+        false
+    }
+    /// Called when a query accepted by this handler is canceled, either by the
+    /// renderer, because its frame navigated or its browser closed, or through
+    /// [MessageRouterBrowserSide::cancel_pending]. `frame` is None if the frame no
+    /// longer exists. No response can be sent for a canceled query.
+    fn on_query_canceled(&self, browser: Browser, frame: Option<Frame>, query_id: i64) {}
+}
+
+/// Used to answer a query accepted by a [MessageRouterHandler]. Responses sent
+/// after the query completed or was canceled are ignored.
+#[derive(Clone)]
+pub struct Callback {
+    router: Weak<BrowserSideInner>,
+    query_id: i64,
+}
+
+impl Callback {
+    /// Sends a successful response. `response` is passed to `onSuccess`.
+    pub fn success(&self, response: &str) {
+        if let Some(router) = self.router.upgrade() {
+            router.respond(self.query_id, Ok(response.to_owned()));
+        }
+    }
+    /// Sends a failure response and completes the query. `error_code` and
+    /// `error_message` are passed to `onFailure`.
+    pub fn failure(&self, error_code: i32, error_message: &str) {
+        if let Some(router) = self.router.upgrade() {
+            router.respond(self.query_id, Err((error_code, error_message.to_owned())));
+        }
+    }
+}
+
+struct PendingQuery {
+    browser: Browser,
+    frame_id: i64,
+    context_id: i32,
+    request_id: i32,
+    persistent: bool,
+    handler: Arc<dyn MessageRouterHandler>,
+}
+
+struct BrowserSideInner {
+    config: MessageRouterConfig,
+    handlers: Mutex<Vec<Arc<dyn MessageRouterHandler>>>,
+    queries: Mutex<HashMap<i64, PendingQuery>>,
+    next_query_id: AtomicI64,
+}
+
+lazy_static::lazy_static! {
+    static ref BROWSER_ROUTERS: Mutex<Vec<Weak<BrowserSideInner>>> = Mutex::new(Vec::new());
+    static ref RENDERER_ROUTERS: Mutex<Vec<Weak<RendererSideInner>>> = Mutex::new(Vec::new());
+}
+
+/// The browser process half of the message router. The router stays active
+/// until the last clone is dropped.
+#[derive(Clone)]
+pub struct MessageRouterBrowserSide(Arc<BrowserSideInner>);
+
+impl MessageRouterBrowserSide {
+    /// Creates a router and starts listening for queries from renderers using the
+    /// same `config`.
+    pub fn new(config: MessageRouterConfig) -> Self {
+        let inner = Arc::new(BrowserSideInner {
+            config,
+            handlers: Mutex::new(Vec::new()),
+            queries: Mutex::new(HashMap::new()),
+            next_query_id: AtomicI64::new(1),
+        });
+        let mut routers = BROWSER_ROUTERS.lock();
+        routers.retain(|router| router.strong_count() > 0);
+        routers.push(Arc::downgrade(&inner));
+        Self(inner)
+    }
+    /// Adds a handler. If `first` is true the handler is asked before all existing
+    /// handlers, otherwise after them. Returns false if the handler was already
+    /// added.
+    pub fn add_handler(&self, handler: Arc<dyn MessageRouterHandler>, first: bool) -> bool {
+        let mut handlers = self.0.handlers.lock();
+        if handlers.iter().any(|existing| Arc::ptr_eq(existing, &handler)) {
+            return false;
+        }
+        if first {
+            handlers.insert(0, handler);
+        } else {
+            handlers.push(handler);
+        }
+        true
+    }
+    /// Removes a handler and cancels all queries it accepted. Returns false if the
+    /// handler wasn't added.
+    pub fn remove_handler(&self, handler: &Arc<dyn MessageRouterHandler>) -> bool {
+        let removed = {
+            let mut handlers = self.0.handlers.lock();
+            let len = handlers.len();
+            handlers.retain(|existing| !Arc::ptr_eq(existing, handler));
+            handlers.len() != len
+        };
+        if removed {
+            self.0.cancel_where(|query| Arc::ptr_eq(&query.handler, handler), true);
+        }
+        removed
+    }
+    /// Cancels all pending queries, optionally only those of `browser` and/or
+    /// `handler`. The renderer is notified with [CANCELED_ERROR_CODE].
+    pub fn cancel_pending(
+        &self,
+        browser: Option<&Browser>,
+        handler: Option<&Arc<dyn MessageRouterHandler>>,
+    ) {
+        let browser_id = browser.map(Browser::get_identifier);
+        self.0.cancel_where(
+            |query| matches_filter(query, browser_id, handler),
+            true,
+        );
+    }
+    /// Returns the number of pending queries, optionally only those of `browser`
+    /// and/or `handler`.
+    pub fn get_pending_count(
+        &self,
+        browser: Option<&Browser>,
+        handler: Option<&Arc<dyn MessageRouterHandler>>,
+    ) -> usize {
+        let browser_id = browser.map(Browser::get_identifier);
+        self.0
+            .queries
+            .lock()
+            .values()
+            .filter(|query| matches_filter(query, browser_id, handler))
+            .count()
+    }
+}
+
+fn matches_filter(
+    query: &PendingQuery,
+    browser_id: Option<i32>,
+    handler: Option<&Arc<dyn MessageRouterHandler>>,
+) -> bool {
+    browser_id.map_or(true, |id| query.browser.get_identifier() == id)
+        && handler.map_or(true, |handler| Arc::ptr_eq(&query.handler, handler))
+}
+
+impl BrowserSideInner {
+    fn on_query(
+        self: &Arc<Self>,
+        browser: Browser,
+        frame: Frame,
+        context_id: i32,
+        request_id: i32,
+        request: &str,
+        persistent: bool,
+    ) {
+        let frame_id = frame.get_identifier().unwrap_or(-1);
+        let query_id = self.next_query_id.fetch_add(1, Ordering::Relaxed);
+        let handlers = self.handlers.lock().clone();
+        for handler in handlers {
+            // The query has to be registered before the handler sees it, since
+            // the handler may answer it synchronously.
+            self.queries.lock().insert(query_id, PendingQuery {
+                browser: browser.clone(),
+                frame_id,
+                context_id,
+                request_id,
+                persistent,
+                handler: handler.clone(),
+            });
+            let callback = Callback {
+                router: Arc::downgrade(self),
+                query_id,
+            };
+            if handler.on_query(browser.clone(), frame.clone(), query_id, request, persistent, callback) {
+                return;
+            }
+            self.queries.lock().remove(&query_id);
+        }
+        self.send_response(
+            &browser,
+            frame_id,
+            context_id,
+            request_id,
+            Err((CANCELED_ERROR_CODE, CANCELED_ERROR_MESSAGE.to_owned())),
+        );
+    }
+
+    fn respond(&self, query_id: i64, response: Result<String, (i32, String)>) {
+        let target = {
+            let mut queries = self.queries.lock();
+            let keep = match (queries.get(&query_id), &response) {
+                (None, _) => return,
+                (Some(query), Ok(_)) => query.persistent,
+                (Some(_), Err(_)) => false,
+            };
+            if keep {
+                queries.get(&query_id).map(|query| (query.browser.clone(), query.frame_id, query.context_id, query.request_id))
+            } else {
+                queries.remove(&query_id).map(|query| (query.browser, query.frame_id, query.context_id, query.request_id))
+            }
+        };
+        if let Some((browser, frame_id, context_id, request_id)) = target {
+            self.send_response(&browser, frame_id, context_id, request_id, response);
+        }
+    }
+
+    fn send_response(
+        &self,
+        browser: &Browser,
+        frame_id: i64,
+        context_id: i32,
+        request_id: i32,
+        response: Result<String, (i32, String)>,
+    ) {
+        let browser = browser.clone();
+        let name = self.config.query_message_name();
+        let send = move || {
+            let frame = match browser.get_frame_byident(frame_id) {
+                Some(frame) => frame,
+                None => return,
+            };
+            let message = ProcessMessage::new(&name);
+            let args = message.get_argument_list();
+            args.set_int(0, context_id);
+            args.set_int(1, request_id);
+            match response {
+                Ok(response) => {
+                    args.set_bool(2, true);
+                    args.set_string(3, &response);
+                }
+                Err((error_code, error_message)) => {
+                    args.set_bool(2, false);
+                    args.set_int(3, error_code);
+                    args.set_string(4, &error_message);
+                }
+            }
+            frame.send_process_message(message);
+        };
+        if TaskRunner::currently_on(ThreadId::UI) {
+            send();
+        } else {
+            TaskRunner::post_task_on(ThreadId::UI, send);
+        }
+    }
+
+    /// Removes all queries matching `predicate` and notifies their handlers. If
+    /// `notify_renderer` is true, the renderer receives a failure response.
+    fn cancel_where(&self, predicate: impl Fn(&PendingQuery) -> bool, notify_renderer: bool) {
+        let canceled = {
+            let mut queries = self.queries.lock();
+            let ids = queries.iter()
+                .filter(|(_, query)| predicate(query))
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+            ids.into_iter()
+                .filter_map(|id| queries.remove(&id).map(|query| (id, query)))
+                .collect::<Vec<_>>()
+        };
+        for (query_id, query) in canceled {
+            if notify_renderer {
+                self.send_response(
+                    &query.browser,
+                    query.frame_id,
+                    query.context_id,
+                    query.request_id,
+                    Err((CANCELED_ERROR_CODE, CANCELED_ERROR_MESSAGE.to_owned())),
+                );
+            }
+            let frame = query.browser.get_frame_byident(query.frame_id);
+            query.handler.on_query_canceled(query.browser, frame, query_id);
+        }
+    }
+
+    fn on_process_message(self: &Arc<Self>, browser: &Browser, frame: &Frame, message: &ProcessMessage) -> bool {
+        let name = match message.get_name() {
+            Some(name) => name,
+            None => return false,
+        };
+        let args = message.get_argument_list();
+        if name == self.config.query_message_name() {
+            if let (Some(context_id), Some(request_id), Some(request)) = (args.get_int(0), args.get_int(1), args.get_string(2)) {
+                let persistent = args.get_bool(3).unwrap_or(false);
+                self.on_query(browser.clone(), frame.clone(), context_id, request_id, &request, persistent);
+            }
+            true
+        } else if name == self.config.cancel_message_name() {
+            if let (Some(context_id), Some(request_id)) = (args.get_int(0), args.get_int(1)) {
+                let browser_id = browser.get_identifier();
+                self.cancel_where(|query| {
+                    query.browser.get_identifier() == browser_id
+                        && query.context_id == context_id
+                        && (request_id == -1 || query.request_id == request_id)
+                }, false);
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn browser_routers() -> Vec<Arc<BrowserSideInner>> {
+    BROWSER_ROUTERS.lock().iter().filter_map(Weak::upgrade).collect()
+}
+
+/// Routes query and cancel messages from the renderer. Returns true if
+/// `message` belonged to a router.
+pub(crate) fn on_browser_process_message(browser: &Browser, frame: &Frame, message: &ProcessMessage) -> bool {
+    browser_routers().iter().any(|router| router.on_process_message(browser, frame, message))
+}
+
+/// Cancels the queries of a frame that is about to navigate. Navigating the main
+/// frame cancels all queries of the browser.
+pub(crate) fn on_before_browse(browser: &Browser, frame: &Frame) {
+    let browser_id = browser.get_identifier();
+    let frame_id = frame.get_identifier();
+    let is_main = frame.is_main();
+    for router in browser_routers() {
+        router.cancel_where(|query| {
+            query.browser.get_identifier() == browser_id
+                && (is_main || Some(query.frame_id) == frame_id)
+        }, false);
+    }
+}
+
+/// Cancels the queries of a browser that is closing or whose render process
+/// terminated.
+pub(crate) fn on_browser_gone(browser: &Browser) {
+    let browser_id = browser.get_identifier();
+    for router in browser_routers() {
+        router.cancel_where(|query| query.browser.get_identifier() == browser_id, false);
+    }
+}
+
+struct PendingRequest {
+    persistent: bool,
+    on_success: Option<V8Value>,
+    on_failure: Option<V8Value>,
+}
+
+struct RendererSideInner {
+    config: MessageRouterConfig,
+    contexts: Mutex<Vec<(i32, V8Context)>>,
+    requests: Mutex<HashMap<(i32, i32), PendingRequest>>,
+    next_context_id: AtomicI32,
+    next_request_id: AtomicI32,
+}
+
+/// The render process half of the message router. The router stays active
+/// until the last clone is dropped.
+#[derive(Clone)]
+pub struct MessageRouterRendererSide(Arc<RendererSideInner>);
+
+impl MessageRouterRendererSide {
+    /// Creates a router that installs the query functions named in `config` into
+    /// every V8 context created from now on.
+    pub fn new(config: MessageRouterConfig) -> Self {
+        let inner = Arc::new(RendererSideInner {
+            config,
+            contexts: Mutex::new(Vec::new()),
+            requests: Mutex::new(HashMap::new()),
+            next_context_id: AtomicI32::new(1),
+            next_request_id: AtomicI32::new(1),
+        });
+        let mut routers = RENDERER_ROUTERS.lock();
+        routers.retain(|router| router.strong_count() > 0);
+        routers.push(Arc::downgrade(&inner));
+        Self(inner)
+    }
+    /// Returns the number of contexts that currently have the query functions
+    /// installed.
+    pub fn get_context_count(&self) -> usize {
+        self.0.contexts.lock().len()
+    }
+}
+
+impl RendererSideInner {
+    fn context_id(&self, context: &V8Context) -> Option<i32> {
+        self.contexts.lock().iter()
+            .find(|(_, existing)| existing.is_same(context))
+            .map(|(id, _)| *id)
+    }
+
+    fn on_context_created(self: &Arc<Self>, context: &V8Context) {
+        let context_id = self.next_context_id.fetch_add(1, Ordering::Relaxed);
+        self.contexts.lock().push((context_id, context.clone()));
+
+        let global = match context.get_global() {
+            Some(global) => global,
+            None => return,
+        };
+        let attributes = V8PropertyAttribute::READ_ONLY | V8PropertyAttribute::DONT_ENUM | V8PropertyAttribute::DONT_DELETE;
+        let router = Arc::downgrade(self);
+        global.set_value_bykey(
+            &self.config.js_query_function,
+            V8Value::new_function(&self.config.js_query_function, move |_, _, args| {
+                match router.upgrade() {
+                    Some(router) => router.query(args),
+                    None => Err("The message router is no longer available".to_owned()),
+                }
+            }),
+            attributes,
+        );
+        let router = Arc::downgrade(self);
+        global.set_value_bykey(
+            &self.config.js_cancel_function,
+            V8Value::new_function(&self.config.js_cancel_function, move |_, _, args| {
+                match router.upgrade() {
+                    Some(router) => router.cancel(args),
+                    None => Ok(V8Value::from(false)),
+                }
+            }),
+            attributes,
+        );
+    }
+
+    fn on_context_released(&self, frame: &Frame, context: &V8Context) {
+        let context_id = {
+            let mut contexts = self.contexts.lock();
+            match contexts.iter().position(|(_, existing)| existing.is_same(context)) {
+                Some(index) => contexts.remove(index).0,
+                None => return,
+            }
+        };
+        let had_requests = {
+            let mut requests = self.requests.lock();
+            let len = requests.len();
+            requests.retain(|(id, _), _| *id != context_id);
+            requests.len() != len
+        };
+        if had_requests {
+            self.send_cancel(frame, context_id, -1);
+        }
+    }
+
+    fn query(&self, args: &[V8Value]) -> Result<V8Value, String> {
+        let object = match args {
+            [object] if object.is_object() => object,
+            _ => return Err("Invalid arguments; expecting a single object".to_owned()),
+        };
+        let request = object.get_value_bykey("request")
+            .and_then(|request| request.get_string_value())
+            .ok_or_else(|| "Invalid arguments; object member 'request' is required and must have type string".to_owned())?;
+        let function_member = |key: &str| -> Result<Option<V8Value>, String> {
+            match object.get_value_bykey(key) {
+                Some(value) if value.is_function() => Ok(Some(value)),
+                Some(value) if !value.is_undefined() => Err(format!("Invalid arguments; object member '{}' must have type function", key)),
+                _ => Ok(None),
+            }
+        };
+        let on_success = function_member("onSuccess")?;
+        let on_failure = function_member("onFailure")?;
+        let persistent = match object.get_value_bykey("persistent") {
+            Some(value) if value.is_bool() => value.get_bool_value().unwrap_or(false),
+            Some(value) if !value.is_undefined() => return Err("Invalid arguments; object member 'persistent' must have type boolean".to_owned()),
+            _ => false,
+        };
+
+        let context = V8Context::get_current().ok_or_else(|| "No current context".to_owned())?;
+        let context_id = self.context_id(&context).ok_or_else(|| "The context has been released".to_owned())?;
+        let frame = context.get_frame().ok_or_else(|| "The context has no frame".to_owned())?;
+
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        self.requests.lock().insert((context_id, request_id), PendingRequest {
+            persistent,
+            on_success,
+            on_failure,
+        });
+
+        let message = ProcessMessage::new(&self.config.query_message_name());
+        let message_args = message.get_argument_list();
+        message_args.set_int(0, context_id);
+        message_args.set_int(1, request_id);
+        message_args.set_string(2, &request);
+        message_args.set_bool(3, persistent);
+        frame.send_process_message(message);
+
+        Ok(V8Value::from(request_id))
+    }
+
+    fn cancel(&self, args: &[V8Value]) -> Result<V8Value, String> {
+        let request_id = match args {
+            [request_id] if request_id.is_int() => request_id.get_int_value().unwrap_or(-1),
+            _ => return Err("Invalid arguments; expecting a single integer".to_owned()),
+        };
+        let context = V8Context::get_current().ok_or_else(|| "No current context".to_owned())?;
+        let context_id = match self.context_id(&context) {
+            Some(context_id) => context_id,
+            None => return Ok(V8Value::from(false)),
+        };
+        let removed = self.requests.lock().remove(&(context_id, request_id)).is_some();
+        if removed {
+            if let Some(frame) = context.get_frame() {
+                self.send_cancel(&frame, context_id, request_id);
+            }
+        }
+        Ok(V8Value::from(removed))
+    }
+
+    fn send_cancel(&self, frame: &Frame, context_id: i32, request_id: i32) {
+        let message = ProcessMessage::new(&self.config.cancel_message_name());
+        let args = message.get_argument_list();
+        args.set_int(0, context_id);
+        args.set_int(1, request_id);
+        frame.send_process_message(message);
+    }
+
+    fn on_process_message(&self, message: &ProcessMessage) -> bool {
+        if message.get_name().as_ref() != Some(&self.config.query_message_name()) {
+            return false;
+        }
+        let args = message.get_argument_list();
+        let (context_id, request_id, success) = match (args.get_int(0), args.get_int(1), args.get_bool(2)) {
+            (Some(context_id), Some(request_id), Some(success)) => (context_id, request_id, success),
+            _ => return true,
+        };
+        let context = match self.contexts.lock().iter().find(|(id, _)| *id == context_id) {
+            Some((_, context)) => context.clone(),
+            None => return true,
+        };
+        let callback = {
+            let mut requests = self.requests.lock();
+            let keep = match requests.get(&(context_id, request_id)) {
+                Some(request) => success && request.persistent,
+                None => return true,
+            };
+            let request = if keep {
+                requests.get(&(context_id, request_id)).map(|request| (request.on_success.clone(), request.on_failure.clone()))
+            } else {
+                requests.remove(&(context_id, request_id)).map(|request| (request.on_success, request.on_failure))
+            };
+            request.and_then(|(on_success, on_failure)| if success { on_success } else { on_failure })
+        };
+        if let Some(callback) = callback {
+            let callback_args = context.execute_in_context(|| if success {
+                vec![V8Value::from(args.get_string(3).unwrap_or_default().as_str())]
+            } else {
+                vec![
+                    V8Value::from(args.get_int(3).unwrap_or(CANCELED_ERROR_CODE)),
+                    V8Value::from(args.get_string(4).unwrap_or_default().as_str()),
+                ]
+            });
+            if let Some(callback_args) = callback_args {
+                callback.execute_function_with_context(context, None, &callback_args);
+            }
+        }
+        true
+    }
+}
+
+fn renderer_routers() -> Vec<Arc<RendererSideInner>> {
+    RENDERER_ROUTERS.lock().iter().filter_map(Weak::upgrade).collect()
+}
+
+/// Installs the query functions of all renderer routers into a new context.
+pub(crate) fn on_context_created(context: &V8Context) {
+    for router in renderer_routers() {
+        router.on_context_created(context);
+    }
+}
+
+/// Drops the pending requests of a released context and tells the browser
+/// process to cancel them.
+pub(crate) fn on_context_released(frame: &Frame, context: &V8Context) {
+    for router in renderer_routers() {
+        router.on_context_released(frame, context);
+    }
+}
+
+/// Delivers responses from the browser process. Returns true if `message`
+/// belonged to a router.
+pub(crate) fn on_render_process_message(message: &ProcessMessage) -> bool {
+    renderer_routers().iter().any(|router| router.on_process_message(message))
+}
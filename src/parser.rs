@@ -0,0 +1,225 @@
+//! URL, MIME type, base64 and URI encoding utilities, using the same
+//! implementations Chromium uses internally.
+
+use crate::{
+    string::{CefString, CefStringList},
+    values::BinaryValue,
+};
+use bitflags::bitflags;
+use cef_sys::{
+    cef_base64decode, cef_base64encode, cef_create_url, cef_format_url_for_security_display,
+    cef_get_extensions_for_mime_type, cef_get_mime_type, cef_parse_url, cef_string_t,
+    cef_uri_unescape_rule_t, cef_uridecode, cef_uriencode, cef_urlparts_t,
+};
+use std::{mem, ptr};
+
+/// The components of a URL, as split by [parse_url] and joined by [create_url].
+/// Components that aren't present are empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UrlParts {
+    /// The complete URL specification.
+    pub spec: String,
+    /// Scheme component not including the colon (e.g., "http").
+    pub scheme: String,
+    /// User name component.
+    pub username: String,
+    /// Password component.
+    pub password: String,
+    /// Host component. This may be a hostname, an IPv4 address or an IPv6 literal
+    /// surrounded by square brackets (e.g., "[2001:db8::1]").
+    pub host: String,
+    /// Port number component.
+    pub port: String,
+    /// Origin contains just the scheme, host, and port from a URL. Equivalent to
+    /// clearing any username and password, replacing the path with a slash, and
+    /// clearing everything after that. This value will be empty for non-standard
+    /// URLs.
+    pub origin: String,
+    /// Path component including the first slash following the host.
+    pub path: String,
+    /// Query string component (i.e., everything following the '?').
+    pub query: String,
+}
+
+impl UrlParts {
+    fn from_raw(raw: cef_urlparts_t) -> UrlParts {
+        let string = |raw: cef_string_t| String::from(unsafe { CefString::from_raw(raw) });
+        UrlParts {
+            spec: string(raw.spec),
+            scheme: string(raw.scheme),
+            username: string(raw.username),
+            password: string(raw.password),
+            host: string(raw.host),
+            port: string(raw.port),
+            origin: string(raw.origin),
+            path: string(raw.path),
+            query: string(raw.query),
+        }
+    }
+}
+
+/// Parses the specified `url` into its component parts. Returns None if the URL
+/// is empty or invalid.
+pub fn parse_url(url: &str) -> Option<UrlParts> {
+    let mut parts: cef_urlparts_t = unsafe { mem::zeroed() };
+    let valid = unsafe { cef_parse_url(CefString::new(url).as_ptr(), &mut parts) } != 0;
+    // Take ownership of the strings even if parsing failed halfway.
+    let parts = UrlParts::from_raw(parts);
+    if valid {
+        Some(parts)
+    } else {
+        None
+    }
+}
+
+/// Creates a URL from the specified `parts`, which must contain a non-empty
+/// spec or a non-empty host and path (at a minimum), but not both. Returns
+/// None if `parts` isn't valid.
+pub fn create_url(parts: &UrlParts) -> Option<String> {
+    let strings = [
+        CefString::new(&parts.spec),
+        CefString::new(&parts.scheme),
+        CefString::new(&parts.username),
+        CefString::new(&parts.password),
+        CefString::new(&parts.host),
+        CefString::new(&parts.port),
+        CefString::new(&parts.origin),
+        CefString::new(&parts.path),
+        CefString::new(&parts.query),
+    ];
+    // The fields borrow from `strings`, which own and free the buffers.
+    let field = |index: usize| unsafe { ptr::read(strings[index].as_ptr()) };
+    let raw = cef_urlparts_t {
+        spec: field(0),
+        scheme: field(1),
+        username: field(2),
+        password: field(3),
+        host: field(4),
+        port: field(5),
+        origin: field(6),
+        path: field(7),
+        query: field(8),
+    };
+    let mut url = CefString::null();
+    if unsafe { cef_create_url(&raw, url.as_ptr_mut()) } != 0 {
+        Some(url.into())
+    } else {
+        None
+    }
+}
+
+/// This is a convenience function for formatting a URL in a concise and human-
+/// friendly way to help users make security-related decisions (or in other
+/// circumstances when people need to distinguish sites, origins, or otherwise-
+/// simplified URLs from each other). Internationalized domain names (IDN) may
+/// be presented in Unicode if the conversion is considered safe. The returned
+/// value will (a) omit the path for standard schemes, excepting file and
+/// filesystem, and (b) omit the port if it is the default for the scheme. Do
+/// not use this for URLs which will be parsed or sent to other applications.
+pub fn format_url_for_security_display(origin_url: &str) -> String {
+    unsafe {
+        CefString::from_userfree(cef_format_url_for_security_display(CefString::new(origin_url).as_ptr()))
+            .map(String::from)
+            .unwrap_or_default()
+    }
+}
+
+/// Returns the mime type for the specified file extension, or None if unknown.
+pub fn get_mime_type(extension: &str) -> Option<String> {
+    unsafe { CefString::from_userfree(cef_get_mime_type(CefString::new(extension).as_ptr())) }
+        .map(String::from)
+        .filter(|mime_type| !mime_type.is_empty())
+}
+
+/// Returns the extensions associated with the given mime type. This should be
+/// passed in lower case. There could be multiple extensions for a given mime
+/// type, like "html,htm" for "text/html", or "txt,text,html,..." for "text/*".
+pub fn get_extensions_for_mime_type(mime_type: &str) -> Vec<String> {
+    let mut extensions = CefStringList::new();
+    unsafe {
+        cef_get_extensions_for_mime_type(CefString::new(mime_type).as_ptr(), extensions.as_mut_ptr());
+    }
+    extensions.into_iter().map(String::from).collect()
+}
+
+/// Encodes `data` as a base64 string.
+pub fn base64_encode(data: &[u8]) -> String {
+    unsafe {
+        CefString::from_userfree(cef_base64encode(data.as_ptr() as *const _, data.len()))
+            .map(String::from)
+            .unwrap_or_default()
+    }
+}
+
+/// Decodes the base64 encoded string `data`. Returns None if `data` isn't valid
+/// base64.
+pub fn base64_decode(data: &str) -> Option<Vec<u8>> {
+    unsafe { BinaryValue::from_ptr(cef_base64decode(CefString::new(data).as_ptr())) }
+        .map(Vec::from)
+}
+
+/// Escapes characters in `text` which are unsuitable for use as a query
+/// parameter value. Everything except alphanumerics and -_.!~*'() will be
+/// converted to "%XX". If `use_plus` is true spaces will change to "+". The
+/// result is basically the same as encodeURIComponent in JavaScript.
+pub fn uri_encode(text: &str, use_plus: bool) -> String {
+    unsafe {
+        CefString::from_userfree(cef_uriencode(CefString::new(text).as_ptr(), use_plus as _))
+            .map(String::from)
+            .unwrap_or_default()
+    }
+}
+
+bitflags! {
+    /// URI unescape rules passed to [uri_decode].
+    #[derive(Default)]
+    pub struct UriUnescapeRule: crate::CEnumType {
+        /// Unescapes all characters that are not a path separator, a URL special
+        /// character, a space or a spoofing or control character. This is the
+        /// basic rule most other rules build on.
+        const NORMAL = cef_uri_unescape_rule_t::UU_NORMAL.0;
+        /// Unescapes spaces.
+        const SPACES = cef_uri_unescape_rule_t::UU_SPACES.0;
+        /// Unescapes '/' and '\\'. If these characters were unescaped, the
+        /// resulting URL won't be the same as the source one. Moreover, they are
+        /// dangerous because they may cause the path to change. This flag is only
+        /// useful for displaying paths.
+        const PATH_SEPARATORS = cef_uri_unescape_rule_t::UU_PATH_SEPARATORS.0;
+        /// Unescapes various characters that will change the meaning of URLs,
+        /// including '%', '+', '&', '#'. Does not unescape path separators. If
+        /// these characters were unescaped, the resulting URL won't be the same
+        /// as the source one. This flag is only useful for displaying URLs.
+        const URL_SPECIAL_CHARS_EXCEPT_PATH_SEPARATORS = cef_uri_unescape_rule_t::UU_URL_SPECIAL_CHARS_EXCEPT_PATH_SEPARATORS.0;
+        /// Unescapes characters that can be used in spoofing attempts (such as
+        /// LOCK) and control characters (such as BiDi control characters and
+        /// %01). This INCLUDES NULLs. This is used for rare cases such as data:
+        /// URL decoding where the result is binary data.
+        ///
+        /// DO NOT use this for displaying URLs; use [URL_SPECIAL_CHARS_EXCEPT_PATH_SEPARATORS](UriUnescapeRule::URL_SPECIAL_CHARS_EXCEPT_PATH_SEPARATORS)
+        /// instead.
+        const SPOOFING_AND_CONTROL_CHARS = cef_uri_unescape_rule_t::UU_SPOOFING_AND_CONTROL_CHARS.0;
+        /// URL queries use "+" for space. This flag controls that replacement.
+        const REPLACE_PLUS_WITH_SPACE = cef_uri_unescape_rule_t::UU_REPLACE_PLUS_WITH_SPACE.0;
+    }
+}
+
+/// Unescapes `text` and returns the result. Unescaping consists of looking for
+/// the exact pattern "%XX" where each X is a hex digit and converting to the
+/// character with the numerical value of those digits (e.g. "i%20=%203%3b"
+/// unescapes to "i = 3;"). If `convert_to_utf8` is true this function will
+/// attempt to interpret the initial decoded result as UTF-8. If the result is
+/// convertable into UTF-8 it will be returned as converted. Otherwise the
+/// initial decoded result will be returned. The `unescape_rule` parameter
+/// supports further customization the decoding process; an empty rule leaves
+/// `text` unchanged.
+pub fn uri_decode(text: &str, convert_to_utf8: bool, unescape_rule: UriUnescapeRule) -> String {
+    unsafe {
+        CefString::from_userfree(cef_uridecode(
+            CefString::new(text).as_ptr(),
+            convert_to_utf8 as _,
+            cef_uri_unescape_rule_t(unescape_rule.bits()),
+        ))
+        .map(String::from)
+        .unwrap_or_default()
+    }
+}
@@ -86,6 +86,12 @@ pub trait RenderProcessHandlerCallbacks: 'static + Send {
     }
 }
 
+/// Used when the application doesn't provide a render process handler, so that
+/// the crate's built-in message handling still runs.
+pub(crate) struct DefaultRenderProcessHandler;
+
+impl RenderProcessHandlerCallbacks for DefaultRenderProcessHandler {}
+
 #[repr(transparent)]
 pub(crate) struct RenderProcessHandlerWrapper(SendProtector<Box<dyn RenderProcessHandlerCallbacks>>);
 
@@ -160,6 +166,7 @@ cef_callback_impl! {
             frame: Frame: *mut cef_frame_t,
             context: V8Context: *mut cef_v8context_t,
         ) {
+            crate::message_router::on_context_created(&context);
             unsafe{ self.0.get() }.on_context_created(
                 browser,
                 frame,
@@ -173,11 +180,15 @@ cef_callback_impl! {
             frame: Frame: *mut cef_frame_t,
             context: V8Context: *mut cef_v8context_t,
         ) {
-            unsafe{ self.0.get() }.on_context_created(
+            unsafe{ self.0.get() }.on_context_released(
                 browser,
-                frame,
-                context,
+                frame.clone(),
+                context.clone(),
             );
+            crate::message_router::on_context_released(&frame, &context);
+            crate::v8context::reject_pending_promises(&context);
+            crate::v8context::release_instances(&context);
+            crate::v8context::release_helpers(&context);
         }
 
         fn uncaught_exception(
@@ -218,11 +229,24 @@ cef_callback_impl! {
             message: ProcessMessage: *mut cef_process_message_t,
         ) -> std::os::raw::c_int {
             assert_eq!(ProcessId::Browser, source_process);
-            unsafe{ self.0.get() }.on_process_message_received(
-                browser,
-                frame,
-                message
-            ) as std::os::raw::c_int
+            if crate::evaluate::on_render_process_message(&frame, &message)
+                || crate::message_router::on_render_process_message(&message) {
+                return 1;
+            }
+            #[cfg(feature = "typed-messages")]
+            {
+                if crate::message::dispatch(&browser, &frame, &message) {
+                    return 1;
+                }
+            }
+            let handled = unsafe{ self.0.get() }.on_process_message_received(browser, frame, message.clone());
+            #[cfg(feature = "typed-messages")]
+            {
+                if !handled {
+                    crate::message::log_unhandled(&message);
+                }
+            }
+            handled as std::os::raw::c_int
         }
     }
 }
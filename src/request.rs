@@ -266,10 +266,10 @@ impl Request {
             })
             .unwrap_or(ReferrerPolicy::Default)
     }
-    /// Get the post data.
-    pub fn get_post_data(&self) -> PostData {
-        let get_post_data = self.0.get_post_data.unwrap();
-        unsafe { PostData::from_ptr_unchecked(get_post_data(self.0.as_ptr())) }
+    /// Get the post data, or None if the request has no body.
+    pub fn get_post_data(&self) -> Option<PostData> {
+        let get_post_data = self.0.get_post_data?;
+        unsafe { PostData::from_ptr(get_post_data(self.0.as_ptr())) }
     }
     /// Set the post data.
     pub fn set_post_data(&self, post_data: PostData) {
@@ -289,6 +289,14 @@ impl Request {
             HashMap::new()
         }
     }
+    /// Set all header values, replacing the existing ones. The Referer value is
+    /// ignored; use [Request::set_referrer] instead.
+    pub fn set_header_map(&self, header_map: &HashMap<String, Vec<String>>) {
+        if let Some(set_header_map) = self.0.set_header_map {
+            let map = MultiMap::from(header_map);
+            unsafe { set_header_map(self.0.as_ptr(), map.as_ptr()) };
+        }
+    }
     /// Returns the first header value for `name` or None if not found.
     /// Will not return the Referer value if any. Use [Request::get_header_map] instead if
     /// `name` might have multiple values.
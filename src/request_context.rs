@@ -9,7 +9,8 @@ use cef_sys::cef_resolve_callback_t;
 use crate::cookie::CookieManager;
 use crate::values::{StoredValue, DictionaryValue, Value};
 use cef_sys::cef_string_list_t;
-use crate::extension::ExtensionHandler;
+use crate::extension::{ExtensionHandler, LoadExtensionHandler};
+use crate::executor::CallbackFuture;
 use crate::extension::Extension;
 use crate::callback::CompletionCallback;
 use cef_sys::{
@@ -442,6 +443,14 @@ impl RequestContext {
             );
         }
     }
+    /// Async version of [RequestContext::clear_certificate_exceptions].
+    pub fn clear_certificate_exceptions_async(&self) -> CallbackFuture<()> {
+        let (sender, future) = CallbackFuture::new();
+        self.clear_certificate_exceptions(move || {
+            let _ = sender.send(());
+        });
+        future
+    }
     /// Clears all HTTP authentication credentials that were added as part of
     /// handling GetAuthCredentials. `callback` will be executed
     /// on the UI thread after completion.
@@ -453,6 +462,14 @@ impl RequestContext {
             );
         }
     }
+    /// Async version of [RequestContext::clear_http_auth_credentials].
+    pub fn clear_http_auth_credentials_async(&self) -> CallbackFuture<()> {
+        let (sender, future) = CallbackFuture::new();
+        self.clear_http_auth_credentials(move || {
+            let _ = sender.send(());
+        });
+        future
+    }
     /// Clears all active and idle connections that Chromium currently has. This is
     /// only recommended if you have released all other CEF objects but don't yet
     /// want to call Cefshutdown(). `callback` will be executed
@@ -465,6 +482,14 @@ impl RequestContext {
             );
         }
     }
+    /// Async version of [RequestContext::close_all_connections].
+    pub fn close_all_connections_async(&self) -> CallbackFuture<()> {
+        let (sender, future) = CallbackFuture::new();
+        self.close_all_connections(move || {
+            let _ = sender.send(());
+        });
+        future
+    }
     /// Attempts to resolve `origin` to a list of associated IP addresses.
     /// `callback` will be executed on the UI thread after completion.
     pub fn resolve_host(&self, origin: &str, callback: impl 'static + Send + FnOnce(ErrorCode, &[IpAddr])) {
@@ -476,6 +501,18 @@ impl RequestContext {
             )
         }
     }
+    /// Async version of [RequestContext::resolve_host]. Resolves with the
+    /// associated IP addresses, or with the error code if resolution failed.
+    pub fn resolve_host_async(&self, origin: &str) -> CallbackFuture<Result<Vec<IpAddr>, ErrorCode>> {
+        let (sender, future) = CallbackFuture::new();
+        self.resolve_host(origin, move |result, resolved_ips| {
+            let _ = sender.send(match result {
+                ErrorCode::None => Ok(resolved_ips.to_vec()),
+                error => Err(error),
+            });
+        });
+        future
+    }
     /// Load an extension.
     ///
     /// If extension resources will be read from disk using the default load
@@ -532,6 +569,15 @@ impl RequestContext {
             )
         }
     }
+    /// Async version of [RequestContext::load_extension]. Resolves with the loaded
+    /// extension, or with the error code if loading failed. The extension uses a
+    /// handler with CEF's default behavior; use [RequestContext::load_extension]
+    /// to customize it.
+    pub fn load_extension_async(&self, root_directory: &str, manifest: Option<DictionaryValue>) -> CallbackFuture<Result<Extension, ErrorCode>> {
+        let (sender, future) = CallbackFuture::new();
+        self.load_extension(root_directory, manifest, ExtensionHandler::new(LoadExtensionHandler::new(sender)));
+        future
+    }
     /// Returns `true` if this context was used to load the extension identified
     /// by `extension_id`. Other contexts sharing the same storage will also have
     /// access to the extension (see HasExtension). This function must be called on
@@ -18,6 +18,14 @@ use cef_sys::{
 };
 use std::{ptr::null_mut};
 
+mod archive;
+pub use self::archive::{ArchiveKey, MissBehavior, NetworkArchive};
+mod canned;
+mod mock;
+pub use self::mock::{CapturedRequest, MockRoute, RequestMock};
+mod rules;
+pub use self::rules::{RequestRule, RequestRulesHandler, RuleAction};
+
 ref_counted_ptr!{
     pub struct ResourceRequestHandler(*mut cef_resource_request_handler_t);
 }
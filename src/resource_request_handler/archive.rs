@@ -0,0 +1,656 @@
+//! Recording network traffic to disk and replaying it, for deterministic tests
+//! without network access.
+//!
+//! ```ignore
+//! // Record once, with network access:
+//! let archive = NetworkArchive::record("tests/fixtures/home.archive");
+//! // ... return `ResourceRequestHandler::new(archive.clone())` from
+//! // `RequestHandlerCallbacks::get_resource_request_handler`, load the page, then:
+//! archive.save()?;
+//!
+//! // Replay in CI:
+//! let archive = NetworkArchive::replay("tests/fixtures/home.archive")?
+//!     .miss_behavior(MissBehavior::Fail);
+//! // ... load the page, then:
+//! assert!(archive.missed_requests().is_empty());
+//! ```
+//!
+//! Response bodies are recorded after content decoding, so `Content-Encoding`
+//! and `Content-Length` headers aren't recorded.
+
+use super::{canned::CannedResponse, ResourceRequestHandlerCallbacks};
+use crate::{
+    browser::Browser,
+    frame::Frame,
+    load_handler::ErrorCode,
+    request::{PostDataElementType, Request},
+    response::Response,
+    url_request::{
+        RequestCallback, ResourceHandler, ResponseFilter, ResponseFilterCallbacks,
+        ResponseFilterStatus, URLRequestStatus,
+    },
+    ReturnValue,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+const MAGIC: &str = "CEF-NETWORK-ARCHIVE 1";
+
+/// Response headers that describe the encoding of the body on the wire, which
+/// doesn't apply to the decoded body that is recorded.
+const SKIPPED_HEADERS: &[&str] = &["content-encoding", "content-length", "transfer-encoding"];
+
+/// What a replaying [NetworkArchive] does with requests that aren't in the
+/// archive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MissBehavior {
+    /// Fails the request with [ErrorCode::InternetDisconnected].
+    Fail,
+    /// Answers with an empty 404 response.
+    NotFound,
+    /// Lets the request go to the network.
+    Passthrough,
+}
+
+/// Identifies a request in a [NetworkArchive].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ArchiveKey {
+    pub method: String,
+    pub url: String,
+    /// A hash of the request body, if the archive matches bodies and the request
+    /// has one.
+    pub body_hash: Option<String>,
+}
+
+impl fmt::Display for ArchiveKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.url)?;
+        if let Some(body_hash) = &self.body_hash {
+            write!(f, " (body {})", body_hash)?;
+        }
+        Ok(())
+    }
+}
+
+struct Entry {
+    key: ArchiveKey,
+    status: i32,
+    status_text: String,
+    mime_type: String,
+    charset: String,
+    headers: Vec<(String, String)>,
+    body: Arc<Vec<u8>>,
+}
+
+impl Entry {
+    fn new(key: ArchiveKey, response: &Response, body: Vec<u8>) -> Entry {
+        let mut headers: Vec<(String, String)> = response
+            .get_header_map()
+            .into_iter()
+            .filter(|(name, _)| !SKIPPED_HEADERS.contains(&&*name.to_ascii_lowercase()))
+            .flat_map(|(name, values)| values.into_iter().map(move |value| (name.clone(), value)))
+            .collect();
+        headers.sort();
+        Entry {
+            key,
+            status: response.get_status(),
+            status_text: response.get_status_text(),
+            mime_type: response.get_mime_type(),
+            charset: response.get_charset(),
+            headers,
+            body: Arc::new(body),
+        }
+    }
+}
+
+enum Mode {
+    Record {
+        path: PathBuf,
+        /// The bodies of responses in flight, by request identifier.
+        bodies: HashMap<u64, Vec<u8>>,
+    },
+    Replay {
+        /// Entry indices by method and URL.
+        index: HashMap<(String, String), Vec<usize>>,
+        used: Vec<bool>,
+        missed: Vec<ArchiveKey>,
+    },
+}
+
+struct ArchiveState {
+    match_body: bool,
+    miss_behavior: MissBehavior,
+    /// Lock `entries` before `mode` where both are needed.
+    entries: Mutex<Vec<Entry>>,
+    mode: Mutex<Mode>,
+}
+
+/// Records responses into an archive file, or replays them from one.
+///
+/// Install it as the [ResourceRequestHandlerCallbacks] for the requests to
+/// record or replay. Requests are identified by method and URL and, if
+/// [NetworkArchive::match_body] is set, a hash of the request body. When the
+/// same request was recorded several times, the recorded responses are replayed
+/// in order, and the last one is repeated once all were used.
+///
+/// Cloning the archive is cheap; all clones share the same entries.
+#[derive(Clone)]
+pub struct NetworkArchive(Arc<ArchiveState>);
+
+impl NetworkArchive {
+    /// Creates an archive that records responses, to be written to `path` with
+    /// [NetworkArchive::save].
+    pub fn record(path: impl Into<PathBuf>) -> NetworkArchive {
+        NetworkArchive::new(
+            Vec::new(),
+            Mode::Record {
+                path: path.into(),
+                bodies: HashMap::new(),
+            },
+        )
+    }
+    /// Loads an archive written by [NetworkArchive::save] to replay its
+    /// responses. Requests that aren't in the archive fail by default.
+    pub fn replay(path: impl AsRef<Path>) -> io::Result<NetworkArchive> {
+        let entries = read_entries(&fs::read(path)?)?;
+        let mut index: HashMap<(String, String), Vec<usize>> = HashMap::new();
+        for (position, entry) in entries.iter().enumerate() {
+            index
+                .entry((entry.key.method.clone(), entry.key.url.clone()))
+                .or_default()
+                .push(position);
+        }
+        let used = vec![false; entries.len()];
+        Ok(NetworkArchive::new(
+            entries,
+            Mode::Replay {
+                index,
+                used,
+                missed: Vec::new(),
+            },
+        ))
+    }
+    fn new(entries: Vec<Entry>, mode: Mode) -> NetworkArchive {
+        NetworkArchive(Arc::new(ArchiveState {
+            match_body: false,
+            miss_behavior: MissBehavior::Fail,
+            entries: Mutex::new(entries),
+            mode: Mutex::new(mode),
+        }))
+    }
+    /// Sets whether requests with different bodies are told apart. Use the same
+    /// setting for recording and replaying.
+    pub fn match_body(self, match_body: bool) -> Self {
+        self.configure(|state| state.match_body = match_body)
+    }
+    /// Sets what happens to requests that aren't in the archive when replaying.
+    pub fn miss_behavior(self, miss_behavior: MissBehavior) -> Self {
+        self.configure(|state| state.miss_behavior = miss_behavior)
+    }
+    fn configure(mut self, f: impl FnOnce(&mut ArchiveState)) -> Self {
+        match Arc::get_mut(&mut self.0) {
+            Some(state) => f(state),
+            None => log::error!("NetworkArchive can't be configured after it was cloned"),
+        }
+        self
+    }
+
+    /// Returns the number of recorded or loaded responses.
+    pub fn len(&self) -> usize {
+        self.0.entries.lock().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the recorded responses to the archive file. Does nothing when
+    /// replaying.
+    pub fn save(&self) -> io::Result<()> {
+        let path = match &*self.0.mode.lock() {
+            Mode::Record { path, .. } => path.clone(),
+            Mode::Replay { .. } => return Ok(()),
+        };
+        let entries = self.0.entries.lock();
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        write_entries(&mut file, &entries)?;
+        file.flush()
+    }
+
+    /// Returns the archived requests that were never replayed, in the order they
+    /// were recorded. Always empty when recording.
+    pub fn unused_entries(&self) -> Vec<ArchiveKey> {
+        let entries = self.0.entries.lock();
+        match &*self.0.mode.lock() {
+            Mode::Replay { used, .. } => {
+                entries
+                    .iter()
+                    .zip(used)
+                    .filter(|(_, used)| !**used)
+                    .map(|(entry, _)| entry.key.clone())
+                    .collect()
+            }
+            Mode::Record { .. } => Vec::new(),
+        }
+    }
+    /// Returns the requests that weren't found in the archive when replaying, in
+    /// the order they were made.
+    pub fn missed_requests(&self) -> Vec<ArchiveKey> {
+        match &*self.0.mode.lock() {
+            Mode::Replay { missed, .. } => missed.clone(),
+            Mode::Record { .. } => Vec::new(),
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        matches!(*self.0.mode.lock(), Mode::Record { .. })
+    }
+
+    fn key(&self, request: &Request) -> ArchiveKey {
+        ArchiveKey {
+            method: request.get_method().to_ascii_uppercase(),
+            url: request.get_url(),
+            body_hash: if self.0.match_body { body_hash(request) } else { None },
+        }
+    }
+
+    fn record_response(&self, request: &Request, response: &Response, body: Vec<u8>) {
+        let entry = Entry::new(self.key(request), response, body);
+        self.0.entries.lock().push(entry);
+    }
+
+    fn replay_handler(&self, request: &Request) -> Option<ResourceHandler> {
+        let key = self.key(request);
+        let entries = self.0.entries.lock();
+        let mut mode = self.0.mode.lock();
+        let (index, used, missed) = match &mut *mode {
+            Mode::Replay { index, used, missed } => (index, used, missed),
+            Mode::Record { .. } => return None,
+        };
+        let candidates: Vec<usize> = index
+            .get(&(key.method.clone(), key.url.clone()))
+            .map(|candidates| {
+                candidates
+                    .iter()
+                    .copied()
+                    .filter(|&position| !self.0.match_body || entries[position].key.body_hash == key.body_hash)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let position = candidates
+            .iter()
+            .copied()
+            .find(|&position| !used[position])
+            .or_else(|| candidates.last().copied());
+        let handler = match position {
+            Some(position) => {
+                used[position] = true;
+                let entry = &entries[position];
+                CannedResponse {
+                    charset: entry.charset.clone(),
+                    headers: entry.headers.clone(),
+                    body: entry.body.clone(),
+                    ..CannedResponse::new(entry.status, &entry.status_text, &entry.mime_type)
+                }
+            }
+            None => {
+                log::warn!("Request not found in network archive: {}", key);
+                missed.push(key);
+                match self.0.miss_behavior {
+                    MissBehavior::Fail => CannedResponse::error(ErrorCode::InternetDisconnected),
+                    MissBehavior::NotFound => CannedResponse::not_found(),
+                    MissBehavior::Passthrough => return None,
+                }
+            }
+        };
+        Some(ResourceHandler::new(handler))
+    }
+}
+
+impl ResourceRequestHandlerCallbacks for NetworkArchive {
+    fn on_before_resource_load(
+        &self,
+        browser: Option<Browser>,
+        frame: Option<Frame>,
+        request: Request,
+        callback: RequestCallback,
+    ) -> ReturnValue {
+        ReturnValue::Continue
+    }
+    fn get_resource_handler(
+        &self,
+        browser: Option<Browser>,
+        frame: Option<Frame>,
+        request: Request,
+    ) -> Option<ResourceHandler> {
+        self.replay_handler(&request)
+    }
+    fn on_resource_redirect(
+        &self,
+        browser: Option<Browser>,
+        frame: Option<Frame>,
+        request: Request,
+        response: Response,
+        new_url: &mut String,
+    ) {
+        if self.is_recording() {
+            self.record_response(&request, &response, Vec::new());
+        }
+    }
+    fn get_resource_response_filter(
+        &self,
+        browser: Option<Browser>,
+        frame: Option<Frame>,
+        request: Request,
+        response: Response,
+    ) -> Option<ResponseFilter> {
+        match &mut *self.0.mode.lock() {
+            Mode::Record { bodies, .. } => {
+                let identifier = request.get_identifier();
+                bodies.insert(identifier, Vec::new());
+                Some(ResponseFilter::new(RecordFilter {
+                    archive: self.0.clone(),
+                    identifier,
+                }))
+            }
+            Mode::Replay { .. } => None,
+        }
+    }
+    fn on_resource_load_complete(
+        &self,
+        browser: Option<Browser>,
+        frame: Option<Frame>,
+        request: Request,
+        response: Response,
+        status: URLRequestStatus,
+        received_content_length: i64,
+    ) {
+        let body = match &mut *self.0.mode.lock() {
+            Mode::Record { bodies, .. } => bodies.remove(&request.get_identifier()).unwrap_or_default(),
+            Mode::Replay { .. } => return,
+        };
+        if status == URLRequestStatus::Success {
+            self.record_response(&request, &response, body);
+        }
+    }
+}
+
+/// A stable hash of the request body, as 16 hex digits. File elements are
+/// hashed by their path.
+fn body_hash(request: &Request) -> Option<String> {
+    let elements = request.get_post_data()?.get_elements();
+    if elements.is_empty() {
+        return None;
+    }
+    // 64-bit FNV-1a, which unlike the std hashers is the same in every build.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for element in elements {
+        let data = match element.get_type() {
+            PostDataElementType::Bytes => element.get_bytes(),
+            PostDataElementType::File => element.get_file().into_bytes(),
+            PostDataElementType::Empty => continue,
+        };
+        for byte in data {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    Some(format!("{:016x}", hash))
+}
+
+/// Copies the response body to the archive while passing it through.
+struct RecordFilter {
+    archive: Arc<ArchiveState>,
+    identifier: u64,
+}
+
+impl ResponseFilterCallbacks for RecordFilter {
+    fn init_filter(&self) -> bool {
+        true
+    }
+    fn filter(
+        &self,
+        data_in: &[u8],
+        data_in_read: &mut usize,
+        data_out: &mut [u8],
+        data_out_written: &mut usize,
+    ) -> ResponseFilterStatus {
+        let count = data_in.len().min(data_out.len());
+        data_out[..count].copy_from_slice(&data_in[..count]);
+        *data_in_read = count;
+        *data_out_written = count;
+        if let Mode::Record { bodies, .. } = &mut *self.archive.mode.lock() {
+            if let Some(body) = bodies.get_mut(&self.identifier) {
+                body.extend_from_slice(&data_in[..count]);
+            }
+        }
+        if count < data_in.len() {
+            ResponseFilterStatus::NeedMoreData
+        } else {
+            ResponseFilterStatus::Done
+        }
+    }
+}
+
+/// Writes `entries` in the archive format: a header line, then per entry a
+/// block of `name value` lines ending with a `body` line that gives the length
+/// of the raw body bytes following it.
+fn write_entries(out: &mut impl Write, entries: &[Entry]) -> io::Result<()> {
+    writeln!(out, "{}", MAGIC)?;
+    for entry in entries {
+        writeln!(out, "entry")?;
+        writeln!(out, "method {}", entry.key.method)?;
+        writeln!(out, "url {}", entry.key.url)?;
+        if let Some(body_hash) = &entry.key.body_hash {
+            writeln!(out, "body-hash {}", body_hash)?;
+        }
+        writeln!(out, "status {} {}", entry.status, entry.status_text)?;
+        writeln!(out, "mime-type {}", entry.mime_type)?;
+        writeln!(out, "charset {}", entry.charset)?;
+        for (name, value) in &entry.headers {
+            writeln!(out, "header {}: {}", name, value)?;
+        }
+        writeln!(out, "body {}", entry.body.len())?;
+        out.write_all(&entry.body)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Reads the archive format written by [write_entries].
+struct ArchiveReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ArchiveReader<'a> {
+    fn line(&mut self) -> Option<&'a str> {
+        let rest = self.data.get(self.position..).filter(|rest| !rest.is_empty())?;
+        let end = rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+        self.position += end + 1;
+        std::str::from_utf8(&rest[..end]).ok()
+    }
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position.checked_add(len)?)?;
+        self.position += len;
+        Some(bytes)
+    }
+}
+
+fn read_entries(data: &[u8]) -> io::Result<Vec<Entry>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid network archive: {}", message));
+    let mut reader = ArchiveReader { data, position: 0 };
+    if reader.line() != Some(MAGIC) {
+        return Err(invalid("unknown format"));
+    }
+    let mut entries = Vec::new();
+    while let Some(line) = reader.line() {
+        if line != "entry" {
+            return Err(invalid("expected an entry"));
+        }
+        let mut entry = Entry {
+            key: ArchiveKey {
+                method: String::new(),
+                url: String::new(),
+                body_hash: None,
+            },
+            status: 0,
+            status_text: String::new(),
+            mime_type: String::new(),
+            charset: String::new(),
+            headers: Vec::new(),
+            body: Arc::new(Vec::new()),
+        };
+        let body_len = loop {
+            let line = reader.line().ok_or_else(|| invalid("truncated entry"))?;
+            let (name, value) = line.split_at(line.find(' ').unwrap_or(line.len()));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match name {
+                "method" => entry.key.method = value.to_owned(),
+                "url" => entry.key.url = value.to_owned(),
+                "body-hash" => entry.key.body_hash = Some(value.to_owned()),
+                "status" => {
+                    let (status, text) = value.split_at(value.find(' ').unwrap_or(value.len()));
+                    entry.status = status.parse().map_err(|_| invalid("bad status"))?;
+                    entry.status_text = text.trim_start().to_owned();
+                }
+                "mime-type" => entry.mime_type = value.to_owned(),
+                "charset" => entry.charset = value.to_owned(),
+                "header" => {
+                    let colon = value.find(": ").ok_or_else(|| invalid("bad header"))?;
+                    entry.headers.push((value[..colon].to_owned(), value[colon + 2..].to_owned()));
+                }
+                "body" => break value.parse::<usize>().map_err(|_| invalid("bad body length"))?,
+                _ => return Err(invalid("unknown field")),
+            }
+        };
+        let body = reader.bytes(body_len).ok_or_else(|| invalid("truncated body"))?;
+        entry.body = Arc::new(body.to_vec());
+        if reader.bytes(1) != Some(&b"\n"[..]) {
+            return Err(invalid("missing line break after body"));
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry {
+                key: ArchiveKey {
+                    method: "GET".to_owned(),
+                    url: "https://example.com/index.html".to_owned(),
+                    body_hash: None,
+                },
+                status: 200,
+                status_text: "OK".to_owned(),
+                mime_type: "text/html".to_owned(),
+                charset: "utf-8".to_owned(),
+                headers: vec![
+                    ("Cache-Control".to_owned(), "no-cache".to_owned()),
+                    ("Set-Cookie".to_owned(), "a=1; Path=/".to_owned()),
+                    ("Set-Cookie".to_owned(), "b=2".to_owned()),
+                ],
+                body: Arc::new(b"<html>\nentry\nbody 3\n</html>".to_vec()),
+            },
+            Entry {
+                key: ArchiveKey {
+                    method: "POST".to_owned(),
+                    url: "https://example.com/api?q=a b".to_owned(),
+                    body_hash: Some("0123456789abcdef".to_owned()),
+                },
+                status: 404,
+                status_text: "Not Found Here".to_owned(),
+                mime_type: "application/octet-stream".to_owned(),
+                charset: String::new(),
+                headers: Vec::new(),
+                body: Arc::new(vec![0, b'\n', 255, b'\r', b'\n']),
+            },
+            Entry {
+                key: ArchiveKey {
+                    method: "GET".to_owned(),
+                    url: "https://example.com/empty".to_owned(),
+                    body_hash: None,
+                },
+                status: 204,
+                status_text: String::new(),
+                mime_type: String::new(),
+                charset: String::new(),
+                headers: Vec::new(),
+                body: Arc::new(Vec::new()),
+            },
+        ]
+    }
+
+    fn written(entries: &[Entry]) -> Vec<u8> {
+        let mut data = Vec::new();
+        write_entries(&mut data, entries).unwrap();
+        data
+    }
+
+    fn error(data: &[u8]) -> String {
+        match read_entries(data) {
+            Ok(_) => panic!("read an invalid archive"),
+            Err(error) => {
+                assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+                error.to_string()
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_entries() {
+        let expected = entries();
+        let read = read_entries(&written(&expected)).unwrap();
+        assert_eq!(read.len(), expected.len());
+        for (read, expected) in read.iter().zip(&expected) {
+            assert_eq!(read.key, expected.key);
+            assert_eq!(read.status, expected.status);
+            assert_eq!(read.status_text, expected.status_text);
+            assert_eq!(read.mime_type, expected.mime_type);
+            assert_eq!(read.charset, expected.charset);
+            assert_eq!(read.headers, expected.headers);
+            assert_eq!(read.body, expected.body);
+        }
+        assert_eq!(read_entries(&written(&[])).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn rejects_truncated_body() {
+        let data = written(&entries()[..1]);
+        assert!(error(&data[..data.len() - 2]).contains("truncated body"));
+        let header_end = data.iter().position(|c| *c == b'\n').unwrap() + 1;
+        assert!(error(&data[..header_end + 20]).contains("truncated entry"));
+    }
+
+    #[test]
+    fn rejects_missing_newline() {
+        let data = written(&entries()[..1]);
+        assert!(error(&data[..data.len() - 1]).contains("missing line break"));
+        let mut data = data;
+        data.pop();
+        data.push(b'x');
+        assert!(error(&data).contains("missing line break"));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(error(b"").contains("unknown format"));
+        assert!(error(b"CEF-NETWORK-ARCHIVE 2\n").contains("unknown format"));
+        let body_line = |line: &str| format!("{}\nentry\nmethod GET\nurl https://example.com/\n{}\n", MAGIC, line);
+        assert!(error(body_line("status abc").as_bytes()).contains("bad status"));
+        assert!(error(body_line("header no-colon").as_bytes()).contains("bad header"));
+        assert!(error(body_line("body -1").as_bytes()).contains("bad body length"));
+        assert!(error(body_line("cookie a=1").as_bytes()).contains("unknown field"));
+        assert!(error(format!("{}\nnot an entry\n", MAGIC).as_bytes()).contains("expected an entry"));
+    }
+}
@@ -0,0 +1,109 @@
+//! A [ResourceHandler](crate::url_request::ResourceHandler) for responses that
+//! are known in full before the request is made.
+
+use crate::{
+    callback::Callback,
+    load_handler::ErrorCode,
+    request::Request,
+    response::Response,
+    task::{TaskRunner, ThreadId},
+    url_request::{ResourceHandlerCallbacks, ResourceReadHandler, ResourceSkipCallback},
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// Serves a fixed response, optionally after a delay.
+pub(super) struct CannedResponse {
+    pub(super) status: i32,
+    pub(super) status_text: String,
+    pub(super) mime_type: String,
+    pub(super) charset: String,
+    pub(super) headers: Vec<(String, String)>,
+    pub(super) body: Arc<Vec<u8>>,
+    /// Makes CEF follow a redirect to this URL instead of reading the body.
+    pub(super) redirect_url: Option<String>,
+    /// Fails the request with this error instead of responding.
+    pub(super) error: Option<ErrorCode>,
+    /// How long to wait before the response headers are available.
+    pub(super) delay_ms: i64,
+    pub(super) position: usize,
+}
+
+impl CannedResponse {
+    pub(super) fn new(status: i32, status_text: &str, mime_type: &str) -> CannedResponse {
+        CannedResponse {
+            status,
+            status_text: status_text.to_owned(),
+            mime_type: mime_type.to_owned(),
+            charset: String::new(),
+            headers: Vec::new(),
+            body: Arc::new(Vec::new()),
+            redirect_url: None,
+            error: None,
+            delay_ms: 0,
+            position: 0,
+        }
+    }
+    pub(super) fn not_found() -> CannedResponse {
+        CannedResponse::new(404, "Not Found", "text/plain")
+    }
+    pub(super) fn error(error: ErrorCode) -> CannedResponse {
+        CannedResponse {
+            error: Some(error),
+            ..CannedResponse::not_found()
+        }
+    }
+}
+
+impl ResourceHandlerCallbacks for CannedResponse {
+    fn open(&mut self, request: Request, handle_request: &mut bool, callback: Callback) -> bool {
+        if self.delay_ms > 0 && TaskRunner::post_delayed_task_on(ThreadId::IO, move || callback.cont(), self.delay_ms) {
+            *handle_request = false;
+            return true;
+        }
+        *handle_request = true;
+        true
+    }
+    fn get_response_headers(
+        &self,
+        response: Response,
+        response_length: &mut Option<u64>,
+        redirect_url: &mut String,
+    ) {
+        if let Some(error) = self.error {
+            response.set_error(error);
+            return;
+        }
+        response.set_status(self.status);
+        response.set_status_text(&self.status_text);
+        response.set_mime_type(&self.mime_type);
+        if !self.charset.is_empty() {
+            response.set_charset(&self.charset);
+        }
+        let mut headers: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, value) in &self.headers {
+            headers.entry(name.clone()).or_default().push(value.clone());
+        }
+        response.set_header_map(&headers);
+        match &self.redirect_url {
+            Some(url) => {
+                *redirect_url = url.clone();
+                *response_length = Some(0);
+            }
+            None => *response_length = Some(self.body.len() as u64),
+        }
+    }
+    fn skip(&mut self, bytes_to_skip: u64, bytes_skipped: &mut u64, callback: ResourceSkipCallback) -> Result<(), ErrorCode> {
+        let skipped = (bytes_to_skip as usize).min(self.body.len() - self.position);
+        self.position += skipped;
+        *bytes_skipped = skipped as u64;
+        Ok(())
+    }
+    fn read(&mut self, mut handler: ResourceReadHandler) -> Option<ResourceReadHandler> {
+        let buffer = handler.as_buffer_ref();
+        let count = buffer.len().min(self.body.len() - self.position);
+        buffer[..count].copy_from_slice(&self.body[self.position..self.position + count]);
+        self.position += count;
+        handler.set_bytes_read(count as i32);
+        Some(handler)
+    }
+}
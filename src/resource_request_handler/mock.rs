@@ -0,0 +1,491 @@
+//! Stubbing HTTP responses in tests and checking which requests were made.
+//!
+//! ```ignore
+//! let mock = RequestMock::new();
+//! let users = mock
+//!     .route("GET", "https://api.example.com/users/*")
+//!     .json(200, r#"{"name": "Jane"}"#);
+//! let create = mock
+//!     .route("POST", "https://api.example.com/users")
+//!     .respond(201, "")
+//!     .latency(Duration::from_millis(200));
+//! mock.route("GET", "https://cdn.example.com/*").fail(ErrorCode::ConnectionRefused);
+//! // ... return `ResourceRequestHandler::new(mock.clone())` from
+//! // `RequestHandlerCallbacks::get_resource_request_handler`, run the page, then:
+//! assert_eq!(users.hits(), 1);
+//! let request = create.last_request().unwrap();
+//! assert_eq!(request.header("Content-Type"), Some("application/json"));
+//! assert_eq!(request.body_text(), r#"{"name": "John"}"#);
+//! ```
+
+use super::{canned::CannedResponse, MissBehavior, ResourceRequestHandlerCallbacks};
+use crate::{
+    browser::Browser,
+    frame::Frame,
+    load_handler::ErrorCode,
+    request::{PostData, PostDataElementType, Request},
+    url_request::{RequestCallback, ResourceHandler},
+    ReturnValue,
+};
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// A request that was answered by a [RequestMock], as it was when it was made.
+#[derive(Clone)]
+pub struct CapturedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: HashMap<String, Vec<String>>,
+    pub post_data: Option<PostData>,
+    pub request: Request,
+}
+
+impl CapturedRequest {
+    fn new(request: &Request) -> CapturedRequest {
+        CapturedRequest {
+            method: request.get_method().to_ascii_uppercase(),
+            url: request.get_url(),
+            headers: request.get_header_map(),
+            post_data: request.get_post_data(),
+            request: request.clone(),
+        }
+    }
+    /// Returns the first value of the header `name`, which is matched case
+    /// insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .and_then(|(_, values)| values.first())
+            .map(|value| &value[..])
+    }
+    /// Returns the request body. Only in-memory elements are included, not
+    /// files.
+    pub fn body(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for element in self.post_data.iter().flat_map(PostData::get_elements) {
+            if element.get_type() == PostDataElementType::Bytes {
+                body.extend(element.get_bytes());
+            }
+        }
+        body
+    }
+    /// Returns the request body as text, with invalid UTF-8 replaced.
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body()).into_owned()
+    }
+}
+
+struct MockResponse {
+    status: i32,
+    status_text: String,
+    mime_type: String,
+    headers: Vec<(String, String)>,
+    body: Arc<Vec<u8>>,
+    latency: Duration,
+    error: Option<ErrorCode>,
+    redirect_url: Option<String>,
+}
+
+struct RouteState {
+    method: String,
+    pattern: String,
+    response: Mutex<MockResponse>,
+    requests: Mutex<Vec<CapturedRequest>>,
+}
+
+/// A stubbed response for the requests matching a method and URL pattern,
+/// created with [RequestMock::route].
+///
+/// Changes to the response apply to requests made after them. Cloning the
+/// route is cheap; all clones share the same response and captured requests.
+#[derive(Clone)]
+pub struct MockRoute(Arc<RouteState>);
+
+impl MockRoute {
+    fn new(method: &str, pattern: &str) -> MockRoute {
+        MockRoute(Arc::new(RouteState {
+            method: method.to_ascii_uppercase(),
+            pattern: pattern.to_owned(),
+            response: Mutex::new(MockResponse {
+                status: 200,
+                status_text: "OK".to_owned(),
+                mime_type: "text/plain".to_owned(),
+                headers: Vec::new(),
+                body: Arc::new(Vec::new()),
+                latency: Duration::from_millis(0),
+                error: None,
+                redirect_url: None,
+            }),
+            requests: Mutex::new(Vec::new()),
+        }))
+    }
+
+    /// Responds with `status` and `body`. This replaces an earlier
+    /// [MockRoute::fail] or [MockRoute::redirect].
+    pub fn respond(self, status: i32, body: impl Into<Vec<u8>>) -> Self {
+        {
+            let mut response = self.0.response.lock();
+            response.status = status;
+            response.status_text = reason_phrase(status).to_owned();
+            response.body = Arc::new(body.into());
+            response.error = None;
+            if response.redirect_url.take().is_some() {
+                response.headers.retain(|(name, _)| !name.eq_ignore_ascii_case("Location"));
+            }
+        }
+        self
+    }
+    /// Responds with `status` and a JSON `body`.
+    pub fn json(self, status: i32, body: impl Into<Vec<u8>>) -> Self {
+        self.respond(status, body).mime_type("application/json")
+    }
+    /// Sets the MIME type of the response, which is `text/plain` by default.
+    pub fn mime_type(self, mime_type: &str) -> Self {
+        self.0.response.lock().mime_type = mime_type.to_owned();
+        self
+    }
+    /// Adds a response header.
+    pub fn header(self, name: &str, value: &str) -> Self {
+        self.0.response.lock().headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+    /// Delays the response by `latency`.
+    pub fn latency(self, latency: Duration) -> Self {
+        self.0.response.lock().latency = latency;
+        self
+    }
+    /// Fails matching requests with `error` instead of responding, as if the
+    /// network failed.
+    pub fn fail(self, error: ErrorCode) -> Self {
+        self.0.response.lock().error = Some(error);
+        self
+    }
+    /// Redirects matching requests to `location` with a 3xx `status`. The
+    /// redirect is reported to
+    /// [ResourceRequestHandlerCallbacks::on_resource_redirect], and the
+    /// request to `location` can be answered by another route.
+    pub fn redirect(self, status: i32, location: &str) -> Self {
+        {
+            let mut response = self.0.response.lock();
+            response.status = status;
+            response.status_text = reason_phrase(status).to_owned();
+            response.headers.retain(|(name, _)| !name.eq_ignore_ascii_case("Location"));
+            response.headers.push(("Location".to_owned(), location.to_owned()));
+            response.body = Arc::new(Vec::new());
+            response.error = None;
+            response.redirect_url = Some(location.to_owned());
+        }
+        self
+    }
+
+    /// Returns how many requests this route answered.
+    pub fn hits(&self) -> usize {
+        self.0.requests.lock().len()
+    }
+    /// Returns the requests this route answered, in the order they were made.
+    pub fn requests(&self) -> Vec<CapturedRequest> {
+        self.0.requests.lock().clone()
+    }
+    /// Returns the last request this route answered.
+    pub fn last_request(&self) -> Option<CapturedRequest> {
+        self.0.requests.lock().last().cloned()
+    }
+
+    fn matches(&self, method: &str, url: &str) -> bool {
+        (self.0.method == "*" || self.0.method == method) && glob_matches(&self.0.pattern, url)
+    }
+
+    fn handler(&self) -> CannedResponse {
+        let response = self.0.response.lock();
+        if let Some(error) = response.error {
+            return CannedResponse {
+                delay_ms: response.latency.as_millis() as i64,
+                ..CannedResponse::error(error)
+            };
+        }
+        CannedResponse {
+            headers: response.headers.clone(),
+            body: response.body.clone(),
+            redirect_url: response.redirect_url.clone(),
+            delay_ms: response.latency.as_millis() as i64,
+            ..CannedResponse::new(response.status, &response.status_text, &response.mime_type)
+        }
+    }
+}
+
+struct MockState {
+    miss_behavior: MissBehavior,
+    routes: Mutex<Vec<MockRoute>>,
+    unmatched: Mutex<Vec<CapturedRequest>>,
+}
+
+/// Answers requests with stubbed responses declared with [RequestMock::route],
+/// and captures the requests for assertions.
+///
+/// Install it as the [ResourceRequestHandlerCallbacks] for the requests to
+/// stub. Routes are matched in the order they were added. Requests that don't
+/// match any route go to the network by default; see
+/// [RequestMock::miss_behavior].
+///
+/// Cloning the mock is cheap; all clones share the same routes.
+#[derive(Clone)]
+pub struct RequestMock(Arc<MockState>);
+
+impl RequestMock {
+    pub fn new() -> RequestMock {
+        RequestMock(Arc::new(MockState {
+            miss_behavior: MissBehavior::Passthrough,
+            routes: Mutex::new(Vec::new()),
+            unmatched: Mutex::new(Vec::new()),
+        }))
+    }
+    /// Sets what happens to requests that don't match any route.
+    pub fn miss_behavior(mut self, miss_behavior: MissBehavior) -> Self {
+        match Arc::get_mut(&mut self.0) {
+            Some(state) => state.miss_behavior = miss_behavior,
+            None => log::error!("RequestMock can't be configured after it was cloned"),
+        }
+        self
+    }
+
+    /// Adds a route for requests with `method` whose URL matches `url_pattern`,
+    /// in which `*` stands for any sequence of characters. A `method` of `*`
+    /// matches any method. The route responds with an empty 200 response until
+    /// it's configured otherwise.
+    pub fn route(&self, method: &str, url_pattern: &str) -> MockRoute {
+        let route = MockRoute::new(method, url_pattern);
+        self.0.routes.lock().push(route.clone());
+        route
+    }
+
+    /// Returns the requests that didn't match any route, in the order they were
+    /// made.
+    pub fn unmatched_requests(&self) -> Vec<CapturedRequest> {
+        self.0.unmatched.lock().clone()
+    }
+}
+
+impl RequestMock {
+    /// Captures `request` and returns the response of the first matching
+    /// route, or of the miss behavior. Returns None to let the request through.
+    fn answer(&self, request: &Request) -> Option<CannedResponse> {
+        let captured = CapturedRequest::new(request);
+        let route = self
+            .0
+            .routes
+            .lock()
+            .iter()
+            .find(|route| route.matches(&captured.method, &captured.url))
+            .cloned();
+        match route {
+            Some(route) => {
+                let handler = route.handler();
+                route.0.requests.lock().push(captured);
+                Some(handler)
+            }
+            None => {
+                self.0.unmatched.lock().push(captured);
+                match self.0.miss_behavior {
+                    MissBehavior::Fail => Some(CannedResponse::error(ErrorCode::InternetDisconnected)),
+                    MissBehavior::NotFound => Some(CannedResponse::not_found()),
+                    MissBehavior::Passthrough => None,
+                }
+            }
+        }
+    }
+}
+
+impl Default for RequestMock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceRequestHandlerCallbacks for RequestMock {
+    fn on_before_resource_load(
+        &self,
+        browser: Option<Browser>,
+        frame: Option<Frame>,
+        request: Request,
+        callback: RequestCallback,
+    ) -> ReturnValue {
+        ReturnValue::Continue
+    }
+    fn get_resource_handler(
+        &self,
+        browser: Option<Browser>,
+        frame: Option<Frame>,
+        request: Request,
+    ) -> Option<ResourceHandler> {
+        self.answer(&request).map(ResourceHandler::new)
+    }
+}
+
+/// Whether `text` matches `pattern`, in which `*` stands for any sequence of
+/// characters.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let mut rest = match text.strip_prefix(parts.next().unwrap_or("")) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(start) => rest = &rest[start + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+fn reason_phrase(status: i32) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        303 => "See Other",
+        304 => "Not Modified",
+        307 => "Temporary Redirect",
+        308 => "Permanent Redirect",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, url: &str) -> Request {
+        let request = Request::new();
+        request.set_method(method);
+        request.set_url(url);
+        request
+    }
+
+    #[test]
+    fn matches_without_wildcard() {
+        assert!(glob_matches("https://example.com/api", "https://example.com/api"));
+        assert!(!glob_matches("https://example.com/api", "https://example.com/api/users"));
+        assert!(!glob_matches("https://example.com/api", "https://example.com/ap"));
+        assert!(glob_matches("", ""));
+        assert!(!glob_matches("", "a"));
+    }
+
+    #[test]
+    fn matches_leading_wildcard() {
+        assert!(glob_matches("*/users", "https://example.com/api/users"));
+        assert!(glob_matches("*/users", "/users"));
+        assert!(!glob_matches("*/users", "https://example.com/users/1"));
+    }
+
+    #[test]
+    fn matches_trailing_wildcard() {
+        assert!(glob_matches("https://example.com/api/*", "https://example.com/api/users?page=2"));
+        assert!(glob_matches("https://example.com/api/*", "https://example.com/api/"));
+        assert!(!glob_matches("https://example.com/api/*", "https://example.com/apis"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("*", "anything"));
+    }
+
+    #[test]
+    fn matches_inner_wildcards() {
+        assert!(glob_matches("a*a", "aa"));
+        assert!(glob_matches("a*a", "abca"));
+        assert!(!glob_matches("a*a", "a"));
+        assert!(!glob_matches("ab*bc", "abc"));
+        assert!(glob_matches("*/api/*/users*", "https://example.com/api/v2/users?id=1"));
+        assert!(!glob_matches("*/api/*/users*", "https://example.com/api/users"));
+        assert!(glob_matches("a**b", "ab"));
+    }
+
+    #[test]
+    fn first_matching_route_answers() {
+        let mock = RequestMock::new();
+        let users = mock.route("GET", "https://example.com/users/*").respond(200, "user");
+        let any = mock.route("*", "https://example.com/*").respond(404, "");
+        let response = mock.answer(&request("GET", "https://example.com/users/1")).unwrap();
+        assert_eq!((response.status, &response.body[..]), (200, &b"user"[..]));
+        let response = mock.answer(&request("POST", "https://example.com/users/1")).unwrap();
+        assert_eq!(response.status, 404);
+        let response = mock.answer(&request("GET", "https://example.com/other")).unwrap();
+        assert_eq!(response.status, 404);
+        assert_eq!((users.hits(), any.hits()), (1, 2));
+    }
+
+    #[test]
+    fn captures_requests() {
+        let mock = RequestMock::new();
+        let route = mock.route("POST", "https://example.com/api");
+        let api_request = request("post", "https://example.com/api");
+        api_request.set_header_by_name("Content-Type", "application/json", true);
+        assert!(mock.answer(&api_request).is_some());
+        let captured = route.last_request().unwrap();
+        assert_eq!(captured.method, "POST");
+        assert_eq!(captured.url, "https://example.com/api");
+        assert_eq!(captured.header("content-type"), Some("application/json"));
+
+        assert!(mock.answer(&request("GET", "https://example.com/api")).is_none());
+        let unmatched = mock.unmatched_requests();
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!((&unmatched[0].method[..], route.hits()), ("GET", 1));
+    }
+
+    #[test]
+    fn delays_and_fails_responses() {
+        let mock = RequestMock::new();
+        mock.route("GET", "https://slow.example.com/*").respond(200, "late").latency(Duration::from_millis(250));
+        mock.route("GET", "https://down.example.com/*")
+            .fail(ErrorCode::ConnectionRefused)
+            .latency(Duration::from_millis(50));
+        let slow = mock.answer(&request("GET", "https://slow.example.com/")).unwrap();
+        assert_eq!((slow.delay_ms, slow.error), (250, None));
+        let down = mock.answer(&request("GET", "https://down.example.com/")).unwrap();
+        assert_eq!((down.delay_ms, down.error), (50, Some(ErrorCode::ConnectionRefused)));
+    }
+
+    #[test]
+    fn answers_misses_as_configured() {
+        let mock = RequestMock::new().miss_behavior(MissBehavior::Fail);
+        let response = mock.answer(&request("GET", "https://example.com/")).unwrap();
+        assert_eq!(response.error, Some(ErrorCode::InternetDisconnected));
+        let mock = RequestMock::new().miss_behavior(MissBehavior::NotFound);
+        let response = mock.answer(&request("GET", "https://example.com/")).unwrap();
+        assert_eq!((response.status, response.error), (404, None));
+    }
+
+    #[test]
+    fn respond_replaces_redirect() {
+        let route = RequestMock::new()
+            .route("GET", "*")
+            .header("X-Test", "1")
+            .redirect(302, "https://example.com/new");
+        let redirect = route.handler();
+        assert_eq!(redirect.redirect_url.as_deref(), Some("https://example.com/new"));
+        assert!(redirect.headers.contains(&("Location".to_owned(), "https://example.com/new".to_owned())));
+
+        let response = route.respond(200, "ok").handler();
+        assert_eq!((response.status, response.redirect_url), (200, None));
+        assert_eq!(response.headers, vec![("X-Test".to_owned(), "1".to_owned())]);
+    }
+}
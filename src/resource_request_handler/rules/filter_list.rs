@@ -0,0 +1,199 @@
+//! Parsing of filters in Adblock Plus syntax.
+
+use super::{resource_type_bit, RequestRule, RuleAction};
+use crate::request::ResourceType;
+
+/// The order in which filters from a list are evaluated.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum FilterPriority {
+    Important,
+    Exception,
+    Blocking,
+}
+
+/// The resource types matched by a type option.
+fn resource_types(option: &str) -> Option<&'static [ResourceType]> {
+    Some(match option {
+        "script" => &[ResourceType::Script],
+        "image" => &[ResourceType::Image, ResourceType::Favicon],
+        "stylesheet" => &[ResourceType::Stylesheet],
+        "object" | "object-subrequest" => &[ResourceType::Object, ResourceType::PluginResource],
+        "xmlhttprequest" => &[ResourceType::XHR],
+        "subdocument" => &[ResourceType::SubFrame],
+        "font" => &[ResourceType::FontResource],
+        "media" => &[ResourceType::Media],
+        "ping" => &[ResourceType::Ping, ResourceType::CSPReport],
+        "other" => &[
+            ResourceType::SubResource,
+            ResourceType::Worker,
+            ResourceType::SharedWorker,
+            ResourceType::Prefetch,
+            ResourceType::ServiceWorker,
+        ],
+        _ => return None,
+    })
+}
+
+fn mask(resource_types: &[ResourceType]) -> u32 {
+    resource_types.iter().copied().map(resource_type_bit).fold(0, |mask, bit| mask | bit)
+}
+
+/// Whether the text after the last `$` of a filter is a list of options rather
+/// than part of the pattern.
+fn is_option_list(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || b"~,=|._-".contains(&c))
+}
+
+impl RequestRule {
+    /// Parses a single filter in Adblock Plus syntax. Blocking filters become
+    /// [Cancel](RuleAction::Cancel) rules and exception filters (`@@`)
+    /// [Allow](RuleAction::Allow) rules.
+    ///
+    /// Supported are URL patterns and the options `script`, `image`,
+    /// `stylesheet`, `object`, `xmlhttprequest`, `subdocument`, `font`, `media`,
+    /// `ping` and `other` (each optionally negated with `~`), `third-party`,
+    /// `first-party`, `domain=`, `match-case` and `important`. As in Adblock Plus,
+    /// filters without type options don't apply to main frame navigations.
+    ///
+    /// Returns None for comments, element hiding filters, regular expression
+    /// filters and filters with other options, which are ignored.
+    pub fn from_filter(filter: &str) -> Option<RequestRule> {
+        let filter = filter.trim();
+        if filter.is_empty() || filter.starts_with('!') || filter.starts_with('[') {
+            return None;
+        }
+        if ["##", "#@#", "#?#", "#$#"].iter().any(|marker| filter.contains(marker)) {
+            return None;
+        }
+        let (exception, filter) = match filter.strip_prefix("@@") {
+            Some(filter) => (true, filter),
+            None => (false, filter),
+        };
+        let (pattern, options) = match filter.rfind('$') {
+            Some(dollar) if is_option_list(&filter[dollar + 1..]) => (&filter[..dollar], Some(&filter[dollar + 1..])),
+            _ => (filter, None),
+        };
+        if pattern.len() > 1 && pattern.starts_with('/') && pattern.ends_with('/') {
+            return None;
+        }
+        if pattern.is_empty() && options.is_none() {
+            return None;
+        }
+
+        let mut rule = RequestRule::new(if exception { RuleAction::Allow } else { RuleAction::Cancel });
+        let mut included = 0;
+        let mut excluded = 0;
+        let mut important = false;
+        for option in options.into_iter().flat_map(|options| options.split(',')) {
+            let (negated, name) = match option.strip_prefix('~') {
+                Some(name) => (true, name),
+                None => (false, option),
+            };
+            match name {
+                "third-party" | "3p" => rule.third_party = Some(!negated),
+                "first-party" | "1p" => rule.third_party = Some(negated),
+                "match-case" => rule.match_case = true,
+                "important" => important = true,
+                "collapse" => {}
+                _ if !negated && name.starts_with("domain=") => {
+                    for domain in name["domain=".len()..].split('|').filter(|domain| !domain.is_empty()) {
+                        match domain.strip_prefix('~') {
+                            Some(domain) => rule.excluded_domains.push(domain.to_ascii_lowercase()),
+                            None => rule.domains.push(domain.to_ascii_lowercase()),
+                        }
+                    }
+                }
+                _ => {
+                    let types = mask(resource_types(name)?);
+                    if negated {
+                        excluded |= types;
+                    } else {
+                        included |= types;
+                    }
+                }
+            }
+        }
+        let types = if included == 0 {
+            !resource_type_bit(ResourceType::MainFrame)
+        } else {
+            included
+        };
+        rule.resource_types = Some(types & !excluded);
+        if !pattern.is_empty() && pattern != "*" {
+            rule = rule.url(pattern);
+        }
+        rule.priority = if exception {
+            FilterPriority::Exception
+        } else if important {
+            FilterPriority::Important
+        } else {
+            FilterPriority::Blocking
+        };
+        Some(rule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{RequestInfo, RequestUrl};
+    use super::*;
+
+    fn request(url: &str, resource_type: ResourceType, page: &str) -> RequestInfo {
+        RequestInfo {
+            url: RequestUrl::new(url.to_owned()),
+            method: "GET".to_owned(),
+            resource_type,
+            first_party: page.to_owned(),
+        }
+    }
+
+    #[test]
+    fn parses_blocking_filter() {
+        let rule = RequestRule::from_filter("||ads.example^$third-party").unwrap();
+        assert!(matches!(rule.action, RuleAction::Cancel));
+        assert!(rule.priority == FilterPriority::Blocking);
+        assert_eq!(rule.third_party, Some(true));
+        assert_eq!(rule.resource_types, Some(!resource_type_bit(ResourceType::MainFrame)));
+        assert_eq!(rule.pattern.as_ref().and_then(|pattern| pattern.host()), Some("ads.example"));
+        assert!(rule.matches(&request("https://ads.example/banner.js", ResourceType::Script, "https://news.example.com/")));
+        assert!(!rule.matches(&request("https://ads.example/banner.js", ResourceType::Script, "https://ads.example/")));
+        assert!(!rule.matches(&request("https://ads.example/", ResourceType::MainFrame, "https://news.example.com/")));
+    }
+
+    #[test]
+    fn parses_exception_filter() {
+        let rule = RequestRule::from_filter("@@||ads.example^$script").unwrap();
+        assert!(matches!(rule.action, RuleAction::Allow));
+        assert!(rule.priority == FilterPriority::Exception);
+        assert_eq!(rule.resource_types, Some(resource_type_bit(ResourceType::Script)));
+
+        let rule = RequestRule::from_filter("||ads.example^$important").unwrap();
+        assert!(rule.priority == FilterPriority::Important);
+    }
+
+    #[test]
+    fn parses_domain_option() {
+        let rule = RequestRule::from_filter("/banner/*$domain=a.com|~b.a.com,~image").unwrap();
+        assert_eq!(rule.domains, vec!["a.com".to_owned()]);
+        assert_eq!(rule.excluded_domains, vec!["b.a.com".to_owned()]);
+        let url = "https://cdn.example/banner/1.js";
+        assert!(rule.matches(&request(url, ResourceType::Script, "https://www.a.com/")));
+        assert!(!rule.matches(&request(url, ResourceType::Script, "https://b.a.com/")));
+        assert!(!rule.matches(&request(url, ResourceType::Script, "https://c.com/")));
+        assert!(!rule.matches(&request(url, ResourceType::Image, "https://a.com/")));
+    }
+
+    #[test]
+    fn ignores_unsupported_filters() {
+        assert!(RequestRule::from_filter("example.com##.ad").is_none());
+        assert!(RequestRule::from_filter("example.com#@#.ad").is_none());
+        assert!(RequestRule::from_filter("! Title: EasyList").is_none());
+        assert!(RequestRule::from_filter("[Adblock Plus 2.0]").is_none());
+        assert!(RequestRule::from_filter("/banner\\d+/").is_none());
+        assert!(RequestRule::from_filter("||ads.example^$popup").is_none());
+        assert!(RequestRule::from_filter("").is_none());
+    }
+}
@@ -10,6 +10,9 @@ use crate::{
 };
 use std::ptr;
 
+mod static_assets;
+pub use self::static_assets::StaticAssetsSchemeHandler;
+
 ref_counted_ptr!{
     /// Structure that creates [`ResourceHandler`] instances for handling scheme
     /// requests. The functions of this structure will always be called on the IO
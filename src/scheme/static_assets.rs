@@ -0,0 +1,688 @@
+//! Serving static files, such as a bundled web app, from a custom scheme.
+//!
+//! ```ignore
+//! // In `AppCallbacks::on_register_custom_schemes`, in every process:
+//! StaticAssetsSchemeHandler::add_custom_scheme(&registrar, "app");
+//! // In the browser process, after initialization:
+//! StaticAssetsSchemeHandler::from_assets(vec![
+//!     ("index.html", &include_bytes!("../ui/index.html")[..]),
+//!     ("app.js", &include_bytes!("../ui/app.js")[..]),
+//! ])
+//! .spa_fallback(true)
+//! .register("app");
+//! // app://ui/ and app://ui/settings now serve index.html.
+//!
+//! // Content packs can be served straight out of a zip archive:
+//! StaticAssetsSchemeHandler::from_zip("packs/winter.zip")?.register("pack");
+//! ```
+
+use super::{SchemeHandlerFactory, SchemeHandlerFactoryCallbacks, SchemeName};
+use crate::{
+    browser::Browser,
+    callback::Callback,
+    frame::Frame,
+    load_handler::ErrorCode,
+    parser::{self, UriUnescapeRule},
+    request::Request,
+    response::Response,
+    scheme_registrar::{SchemeOptions, SchemeRegistrar},
+    stream::StreamReader,
+    url_request::{
+        RangeResourceHandler, ResourceHandler, ResourceHandlerCallbacks, ResourceReadHandler,
+        ResourceSkipCallback,
+    },
+    zip_reader::{ZipEntry, ZipReader},
+};
+use parking_lot::Mutex;
+use std::{
+    borrow::Cow,
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::Hasher,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+/// How many bytes of decompressed zip entries are kept in memory.
+const ZIP_CACHE_SIZE: usize = 64 * 1024 * 1024;
+
+struct Asset {
+    data: Cow<'static, [u8]>,
+    etag: String,
+}
+
+impl Asset {
+    fn new(data: Cow<'static, [u8]>) -> Asset {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&data);
+        Asset {
+            etag: format!("\"{:016x}\"", hasher.finish()),
+            data,
+        }
+    }
+}
+
+/// Lets a [Cursor] read an [Asset] without copying it.
+struct AssetBytes(Arc<Asset>);
+
+impl AsRef<[u8]> for AssetBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0.data
+    }
+}
+
+/// The body of a response. Embedded assets are served from memory; files are
+/// read as needed, so that a range request only reads the bytes it covers.
+enum Body {
+    Memory(Cursor<AssetBytes>),
+    File { file: fs::File, len: u64 },
+}
+
+impl Body {
+    fn memory(asset: Arc<Asset>) -> Body {
+        Body::Memory(Cursor::new(AssetBytes(asset)))
+    }
+    fn len(&self) -> u64 {
+        match self {
+            Body::Memory(cursor) => cursor.get_ref().as_ref().len() as u64,
+            Body::File { len, .. } => *len,
+        }
+    }
+}
+
+impl Read for Body {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Body::Memory(cursor) => cursor.read(buffer),
+            Body::File { file, .. } => file.read(buffer),
+        }
+    }
+}
+
+impl Seek for Body {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        match self {
+            Body::Memory(cursor) => cursor.seek(position),
+            Body::File { file, .. } => file.seek(position),
+        }
+    }
+}
+
+/// Recently read files of a zip archive, so that repeated requests, like the
+/// range requests of a media element that seeks, don't read the archive again.
+struct ZipCache {
+    archive: PathBuf,
+    /// The files by name, least recently used first.
+    files: Mutex<Vec<(String, Arc<Asset>)>>,
+}
+
+impl ZipCache {
+    /// Returns the file `name`, reading it from the archive unless it's cached
+    /// with the same `etag`. Files larger than [ZIP_CACHE_SIZE] aren't cached.
+    fn get(&self, name: &str, etag: String) -> io::Result<Arc<Asset>> {
+        {
+            let mut files = self.files.lock();
+            let index = files.iter().position(|(cached, asset)| cached == name && asset.etag == etag);
+            if let Some(index) = index {
+                let file = files.remove(index);
+                let asset = file.1.clone();
+                files.push(file);
+                return Ok(asset);
+            }
+        }
+        let asset = Arc::new(Asset {
+            data: Cow::Owned(read_zip_entry(&self.archive, name)?),
+            etag,
+        });
+        if asset.data.len() <= ZIP_CACHE_SIZE {
+            let mut files = self.files.lock();
+            files.retain(|(cached, _)| cached != name);
+            files.push((name.to_owned(), asset.clone()));
+            let mut size: usize = files.iter().map(|(_, asset)| asset.data.len()).sum();
+            while size > ZIP_CACHE_SIZE {
+                size -= files.remove(0).1.data.len();
+            }
+        }
+        Ok(asset)
+    }
+}
+
+enum Source {
+    Embedded(HashMap<String, Arc<Asset>>),
+    Directory(PathBuf),
+    Zip {
+        /// The files in the archive by path, read when the handler was created.
+        entries: HashMap<String, ZipEntry>,
+        cache: Arc<ZipCache>,
+    },
+}
+
+/// An asset found for a request. Files are only opened once it's clear that the
+/// body has to be sent.
+enum Found {
+    Embedded(Arc<Asset>),
+    File { path: PathBuf, etag: String },
+    /// A file in a zip archive, which is read on the thread that needs it unless
+    /// it's cached.
+    ZipEntry { cache: Arc<ZipCache>, name: String, etag: String },
+}
+
+impl Found {
+    fn etag(&self) -> &str {
+        match self {
+            Found::Embedded(asset) => &asset.etag,
+            Found::File { etag, .. } => etag,
+            Found::ZipEntry { etag, .. } => etag,
+        }
+    }
+    fn load(self) -> Option<Body> {
+        match self {
+            Found::Embedded(asset) => Some(Body::memory(asset)),
+            Found::File { path, .. } => match open_file(&path) {
+                Ok(body) => Some(body),
+                Err(error) => {
+                    log::error!("failed to open {}: {}", path.display(), error);
+                    None
+                }
+            },
+            Found::ZipEntry { cache, name, etag } => match cache.get(&name, etag) {
+                Ok(asset) => Some(Body::memory(asset)),
+                Err(error) => {
+                    log::error!("failed to read {} from {}: {}", name, cache.archive.display(), error);
+                    None
+                }
+            },
+        }
+    }
+}
+
+struct Config {
+    source: Source,
+    index_file: String,
+    spa_fallback: bool,
+    not_found_page: Option<String>,
+    charset: String,
+}
+
+/// A [SchemeHandlerFactory] implementation that serves static files from memory,
+/// from a directory or from a zip archive.
+///
+/// Paths are resolved relative to the root of the assets; the host of the URL
+/// is ignored. Requests for directories serve their index file. Responses
+/// carry an `ETag`, and requests whose `If-None-Match` header matches it get an
+/// empty 304 response. `Range` requests are answered like
+/// [RangeResourceHandler] does, so media can be seeked. Paths that try to escape
+/// the root, also through symbolic links, or don't exist get a 404 response.
+#[derive(Clone)]
+pub struct StaticAssetsSchemeHandler(Arc<Config>);
+
+impl StaticAssetsSchemeHandler {
+    /// Serves the given files, e.g. embedded with `include_bytes!`. Paths are
+    /// relative to the root and use `/` as separator, like `"css/app.css"`.
+    pub fn from_assets<I, P, D>(assets: I) -> Self
+    where
+        I: IntoIterator<Item = (P, D)>,
+        P: AsRef<str>,
+        D: Into<Cow<'static, [u8]>>,
+    {
+        let assets = assets
+            .into_iter()
+            .map(|(path, data)| {
+                let path = path.as_ref().trim_start_matches('/').to_owned();
+                (path, Arc::new(Asset::new(data.into())))
+            })
+            .collect();
+        Self::with_source(Source::Embedded(assets))
+    }
+    /// Serves the files in the directory `root`. Files are read when they're
+    /// requested. Symbolic links are followed as long as they point to a file
+    /// inside `root`.
+    pub fn from_directory(root: impl Into<PathBuf>) -> Self {
+        Self::with_source(Source::Directory(root.into()))
+    }
+    /// Serves the files in the zip archive at `path` without extracting it. The
+    /// list of files is read right away; their contents are read from the
+    /// archive when they're requested. The most recently requested files are
+    /// kept in memory, up to 64 MiB.
+    pub fn from_zip(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let stream = StreamReader::from_file(&path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("can't open {}", path.display())))?;
+        let mut zip = ZipReader::new(stream)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("{} isn't a zip archive", path.display())))?;
+        let entries = zip
+            .entries()
+            .filter(|entry| !entry.name.ends_with('/'))
+            .map(|entry| (entry.name.trim_start_matches('/').to_owned(), entry))
+            .collect();
+        let cache = Arc::new(ZipCache {
+            archive: path,
+            files: Mutex::new(Vec::new()),
+        });
+        Ok(Self::with_source(Source::Zip { entries, cache }))
+    }
+    fn with_source(source: Source) -> Self {
+        StaticAssetsSchemeHandler(Arc::new(Config {
+            source,
+            index_file: "index.html".to_owned(),
+            spa_fallback: false,
+            not_found_page: None,
+            charset: "utf-8".to_owned(),
+        }))
+    }
+
+    /// Sets the file served for directories. Defaults to `index.html`.
+    pub fn index_file(self, name: &str) -> Self {
+        self.configure(|config| config.index_file = name.to_owned())
+    }
+    /// If enabled, requests for paths that don't exist and don't have a file
+    /// extension serve the root index file, so that a single page app can do its
+    /// own routing. Disabled by default.
+    pub fn spa_fallback(self, enabled: bool) -> Self {
+        self.configure(|config| config.spa_fallback = enabled)
+    }
+    /// Sets the asset served with the 404 status for paths that don't exist.
+    /// Defaults to a plain text message.
+    pub fn not_found_page(self, path: &str) -> Self {
+        self.configure(|config| config.not_found_page = Some(path.to_owned()))
+    }
+    /// Sets the charset reported for text files. Defaults to `utf-8`.
+    pub fn charset(self, charset: &str) -> Self {
+        self.configure(|config| config.charset = charset.to_owned())
+    }
+    fn configure(mut self, f: impl FnOnce(&mut Config)) -> Self {
+        match Arc::get_mut(&mut self.0) {
+            Some(config) => f(config),
+            None => log::error!("StaticAssetsSchemeHandler can't be configured after it was cloned"),
+        }
+        self
+    }
+
+    /// The options used by [StaticAssetsSchemeHandler::add_custom_scheme]: the
+    /// scheme is standard, secure, and allows CORS and Fetch API requests.
+    pub fn scheme_options() -> SchemeOptions {
+        SchemeOptions::STANDARD | SchemeOptions::SECURE | SchemeOptions::CORS_ENABLED | SchemeOptions::FETCH_ENABLED
+    }
+    /// Registers `scheme_name` with [StaticAssetsSchemeHandler::scheme_options].
+    /// Like any custom scheme, this must be done in
+    /// [AppCallbacks::on_register_custom_schemes](crate::app::AppCallbacks::on_register_custom_schemes)
+    /// in all processes.
+    pub fn add_custom_scheme(registrar: &SchemeRegistrar, scheme_name: &str) -> bool {
+        registrar.add_custom_scheme(scheme_name, Self::scheme_options())
+    }
+    /// Registers a [SchemeHandlerFactory] serving these assets for the custom
+    /// scheme `scheme_name` with the global request context. Returns false if
+    /// an error occurs.
+    pub fn register(&self, scheme_name: &str) -> bool {
+        SchemeHandlerFactory::new(self.clone()).register(SchemeName::Custom { name: scheme_name })
+    }
+
+    /// Returns the asset at `path`, which has already been checked for
+    /// traversal.
+    fn find(&self, path: &str) -> Option<Found> {
+        match &self.0.source {
+            Source::Embedded(assets) => assets.get(path).cloned().map(Found::Embedded),
+            Source::Directory(root) => {
+                let path = path.split('/').fold(root.clone(), |path, segment| path.join(segment));
+                let path = fs::canonicalize(path).ok()?;
+                if !path.starts_with(fs::canonicalize(root).ok()?) {
+                    log::warn!("{} is outside of {}", path.display(), root.display());
+                    return None;
+                }
+                let metadata = fs::metadata(&path).ok().filter(|metadata| metadata.is_file())?;
+                let modified = metadata.modified().ok()
+                    .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                    .map(|modified| modified.as_nanos())
+                    .unwrap_or(0);
+                Some(Found::File {
+                    path,
+                    etag: format!("\"{:x}-{:x}\"", metadata.len(), modified),
+                })
+            }
+            Source::Zip { entries, cache } => {
+                let entry = entries.get(path)?;
+                let modified = entry.modified.as_ref().map(|modified| modified.timestamp()).unwrap_or(0);
+                Some(Found::ZipEntry {
+                    cache: cache.clone(),
+                    name: entry.name.clone(),
+                    etag: format!("\"{:x}-{:x}\"", entry.size, modified),
+                })
+            }
+        }
+    }
+
+    /// Resolves a URL path to an asset and the path it was found at.
+    fn resolve(&self, url_path: &str) -> Option<(String, Found)> {
+        let path = normalize_path(url_path)?;
+        let index = &self.0.index_file;
+        let candidates = if path.is_empty() || path.ends_with('/') {
+            vec![format!("{}{}", path, index)]
+        } else {
+            vec![path.clone(), format!("{}/{}", path, index)]
+        };
+        for candidate in candidates {
+            if let Some(found) = self.find(&candidate) {
+                return Some((candidate, found));
+            }
+        }
+        let last_segment = path.rsplit('/').next().unwrap_or("");
+        if self.0.spa_fallback && !last_segment.contains('.') {
+            return self.find(index).map(|found| (index.clone(), found));
+        }
+        None
+    }
+
+    fn respond(&self, request: &Request) -> StaticResponse {
+        let method = request.get_method();
+        let head = method.eq_ignore_ascii_case("HEAD");
+        if !head && !method.eq_ignore_ascii_case("GET") {
+            let mut response = StaticResponse::text(405, "Method Not Allowed");
+            response.headers.push(("Allow", "GET, HEAD".to_owned()));
+            return response;
+        }
+        let url_path = parser::parse_url(&request.get_url()).map(|parts| parts.path).unwrap_or_default();
+        let mut response = match self.resolve(&url_path) {
+            Some((path, found)) => {
+                let etag = found.etag().to_owned();
+                let not_modified = request
+                    .get_header_by_name("If-None-Match")
+                    .map(|header| etag_matches(&header, &etag))
+                    .unwrap_or(false);
+                if not_modified {
+                    let mut response = StaticResponse::empty(304, "Not Modified");
+                    response.headers.push(("ETag", etag));
+                    response
+                } else {
+                    match found.load() {
+                        Some(body) => {
+                            let mut response = self.asset_response(200, "OK", &path, body);
+                            response.headers.insert(0, ("ETag", etag));
+                            response
+                        }
+                        None => StaticResponse::text(500, "Internal Server Error"),
+                    }
+                }
+            }
+            None => self.not_found(),
+        };
+        if head {
+            response.body = None;
+        }
+        response
+    }
+
+    fn asset_response(&self, status: i32, status_text: &'static str, path: &str, body: Body) -> StaticResponse {
+        let mime_type = mime_type(path);
+        let charset = if is_text(&mime_type) { self.0.charset.clone() } else { String::new() };
+        StaticResponse {
+            status,
+            status_text,
+            mime_type,
+            charset,
+            headers: vec![("Cache-Control", "no-cache".to_owned())],
+            length: body.len(),
+            body: Some(body),
+            offset: 0,
+        }
+    }
+
+    fn not_found(&self) -> StaticResponse {
+        let page = self.0.not_found_page.as_ref()
+            .and_then(|path| Some((path, self.find(path.trim_start_matches('/'))?.load()?)));
+        match page {
+            Some((path, body)) => self.asset_response(404, "Not Found", path, body),
+            None => StaticResponse::text(404, "Not Found"),
+        }
+    }
+}
+
+impl SchemeHandlerFactoryCallbacks for StaticAssetsSchemeHandler {
+    fn create(
+        &self,
+        browser: Browser,
+        frame: Frame,
+        scheme_name: &str,
+        request: Request,
+    ) -> Option<ResourceHandler> {
+        // Files are looked up in `open`, which may block, unlike `create`.
+        Some(ResourceHandler::new(StaticAssetHandler {
+            assets: self.clone(),
+            response: None,
+            range: None,
+        }))
+    }
+}
+
+fn open_file(path: &Path) -> io::Result<Body> {
+    let file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    Ok(Body::File { file, len })
+}
+
+/// Reads the file `name` from the zip archive at `archive`.
+fn read_zip_entry(archive: &Path, name: &str) -> io::Result<Vec<u8>> {
+    let stream = StreamReader::from_file(archive)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "can't open the archive"))?;
+    let mut zip = ZipReader::new(stream)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a zip archive"))?;
+    if !zip.move_to_file(name, true) || !zip.open_file(None) {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no such file in the archive"));
+    }
+    let mut data = Vec::new();
+    zip.read_to_end(&mut data)?;
+    if !zip.close_file() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "checksum mismatch"));
+    }
+    Ok(data)
+}
+
+/// Decodes `url_path` and returns it without leading slash. Returns None if a
+/// segment would escape the root.
+fn normalize_path(url_path: &str) -> Option<String> {
+    let rules = UriUnescapeRule::NORMAL
+        | UriUnescapeRule::SPACES
+        | UriUnescapeRule::PATH_SEPARATORS
+        | UriUnescapeRule::URL_SPECIAL_CHARS_EXCEPT_PATH_SEPARATORS;
+    let decoded = parser::uri_decode(url_path, true, rules);
+    let mut segments = Vec::new();
+    for segment in decoded.split(&['/', '\\'][..]) {
+        match segment {
+            "" | "." => (),
+            ".." => return None,
+            segment if segment.contains(&[':', '\0'][..]) => return None,
+            segment => segments.push(segment),
+        }
+    }
+    let mut path = segments.join("/");
+    if decoded.ends_with('/') && !path.is_empty() {
+        path.push('/');
+    }
+    Some(path)
+}
+
+/// Returns true if an `If-None-Match` header matches `etag`, using the weak
+/// comparison.
+fn etag_matches(header: &str, etag: &str) -> bool {
+    let weak = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+    header.split(',').any(|tag| tag.trim() == "*" || weak(tag) == weak(etag))
+}
+
+fn mime_type(path: &str) -> String {
+    let extension = match path.rsplit('/').next().and_then(|name| name.rfind('.').map(|dot| &name[dot + 1..])) {
+        Some(extension) => extension.to_ascii_lowercase(),
+        None => return "application/octet-stream".to_owned(),
+    };
+    parser::get_mime_type(&extension).unwrap_or_else(|| match extension.as_str() {
+        "mjs" => "text/javascript".to_owned(),
+        "wasm" => "application/wasm".to_owned(),
+        "map" => "application/json".to_owned(),
+        _ => "application/octet-stream".to_owned(),
+    })
+}
+
+fn is_text(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || mime_type.ends_with("+xml")
+        || mime_type.ends_with("+json")
+        || ["application/javascript", "application/json", "application/xml"].contains(&mime_type)
+}
+
+struct StaticResponse {
+    status: i32,
+    status_text: &'static str,
+    mime_type: String,
+    charset: String,
+    headers: Vec<(&'static str, String)>,
+    length: u64,
+    body: Option<Body>,
+    offset: u64,
+}
+
+impl StaticResponse {
+    fn empty(status: i32, status_text: &'static str) -> StaticResponse {
+        StaticResponse {
+            status,
+            status_text,
+            mime_type: String::new(),
+            charset: String::new(),
+            headers: Vec::new(),
+            length: 0,
+            body: None,
+            offset: 0,
+        }
+    }
+    fn text(status: i32, status_text: &'static str) -> StaticResponse {
+        let message = format!("{} {}", status, status_text);
+        StaticResponse {
+            mime_type: "text/plain".to_owned(),
+            charset: "utf-8".to_owned(),
+            length: message.len() as u64,
+            body: Some(Body::memory(Arc::new(Asset {
+                data: Cow::Owned(message.into_bytes()),
+                etag: String::new(),
+            }))),
+            ..StaticResponse::empty(status, status_text)
+        }
+    }
+    fn remaining(&self) -> u64 {
+        match self.body {
+            Some(_) => self.length.saturating_sub(self.offset),
+            None => 0,
+        }
+    }
+}
+
+struct StaticAssetHandler {
+    assets: StaticAssetsSchemeHandler,
+    response: Option<StaticResponse>,
+    /// Serves successful responses, so that media can be seeked.
+    range: Option<RangeResourceHandler<Body>>,
+}
+
+impl ResourceHandlerCallbacks for StaticAssetHandler {
+    fn open(&mut self, request: Request, handle_request: &mut bool, callback: Callback) -> bool {
+        let mut response = self.assets.respond(&request);
+        if let (200, Some(body)) = (response.status, response.body.take()) {
+            match RangeResourceHandler::new(body, &response.mime_type) {
+                Ok(range) => {
+                    let mut range = range.charset(&response.charset);
+                    for (name, value) in &response.headers {
+                        range = match *name {
+                            "ETag" => range.etag(value),
+                            name => range.header(name, value),
+                        };
+                    }
+                    let opened = range.open(request, handle_request, callback);
+                    self.range = Some(range);
+                    return opened;
+                }
+                Err(error) => {
+                    log::error!("failed to read response: {}", error);
+                    response = StaticResponse::text(500, "Internal Server Error");
+                }
+            }
+        }
+        self.response = Some(response);
+        *handle_request = true;
+        true
+    }
+    fn get_response_headers(
+        &self,
+        response: Response,
+        response_length: &mut Option<u64>,
+        redirect_url: &mut String,
+    ) {
+        if let Some(range) = &self.range {
+            return range.get_response_headers(response, response_length, redirect_url);
+        }
+        let static_response = match &self.response {
+            Some(static_response) => static_response,
+            None => {
+                response.set_error(ErrorCode::Failed);
+                return;
+            }
+        };
+        response.set_status(static_response.status);
+        response.set_status_text(static_response.status_text);
+        if !static_response.mime_type.is_empty() {
+            response.set_mime_type(&static_response.mime_type);
+        }
+        if !static_response.charset.is_empty() {
+            response.set_charset(&static_response.charset);
+        }
+        for (name, value) in &static_response.headers {
+            response.set_header_by_name(name, value, true);
+        }
+        *response_length = Some(if static_response.body.is_some() { static_response.length } else { 0 });
+    }
+    fn skip(&mut self, bytes_to_skip: u64, bytes_skipped: &mut u64, callback: ResourceSkipCallback) -> Result<(), ErrorCode> {
+        if let Some(range) = &mut self.range {
+            return range.skip(bytes_to_skip, bytes_skipped, callback);
+        }
+        let response = self.response.as_mut().ok_or(ErrorCode::Failed)?;
+        let remaining = response.remaining();
+        if remaining == 0 && bytes_to_skip > 0 {
+            return Err(ErrorCode::RequestRangeNotSatisfiable);
+        }
+        let skipped = bytes_to_skip.min(remaining);
+        response.offset += skipped;
+        *bytes_skipped = skipped;
+        Ok(())
+    }
+    fn read(&mut self, mut handler: ResourceReadHandler) -> Option<ResourceReadHandler> {
+        if let Some(range) = &mut self.range {
+            return range.read(handler);
+        }
+        let response = match self.response.as_mut() {
+            Some(response) => response,
+            None => {
+                handler.set_error(ErrorCode::Failed);
+                return Some(handler);
+            }
+        };
+        let buffer = handler.as_buffer_ref();
+        let count = (response.remaining().min(buffer.len() as u64)) as usize;
+        let offset = response.offset;
+        let result = match &mut response.body {
+            Some(body) if count > 0 => body.seek(SeekFrom::Start(offset)).and_then(|_| body.read(&mut buffer[..count])),
+            _ => Ok(0),
+        };
+        match result {
+            Ok(count) => {
+                response.offset += count as u64;
+                handler.set_bytes_read(count as i32);
+            }
+            Err(error) => {
+                log::error!("failed to read response: {}", error);
+                handler.set_error(ErrorCode::Failed);
+            }
+        }
+        Some(handler)
+    }
+    fn cancel(&mut self) {
+        self.response = None;
+        self.range = None;
+    }
+}
@@ -0,0 +1,376 @@
+//! A lightweight HTTP and WebSocket server for communicating with other
+//! applications on localhost.
+//!
+//! ```ignore
+//! struct Tooling;
+//!
+//! impl ServerHandlerCallbacks for Tooling {
+//!     fn on_server_created(&self, server: Server) {
+//!         if server.is_running() {
+//!             println!("Listening on http://{}/", server.get_address());
+//!         }
+//!     }
+//!     fn on_http_request(&self, server: Server, connection_id: i32, client_address: &str, request: Request) {
+//!         // The URL includes the server address, e.g. "http://127.0.0.1:8099/status".
+//!         if request.get_url().ends_with("/status") {
+//!             server.send_http_200_response(connection_id, "application/json", br#"{"ok": true}"#);
+//!         } else {
+//!             server.send_http_404_response(connection_id);
+//!         }
+//!     }
+//!     fn on_web_socket_request(&self, server: Server, connection_id: i32, client_address: &str, request: Request, callback: WebSocketRequestCallback) {
+//!         callback.accept();
+//!     }
+//!     fn on_web_socket_message(&self, server: Server, connection_id: i32, data: &[u8]) {
+//!         server.send_web_socket_message(connection_id, data);
+//!     }
+//! }
+//!
+//! Server::create("127.0.0.1", 8099, 10, Tooling);
+//! ```
+
+use crate::{
+    callback::Callback,
+    multimap::MultiMap,
+    refcounted::{RefCountedPtr, Wrapper},
+    request::Request,
+    string::CefString,
+    task::TaskRunner,
+};
+use cef_sys::{
+    cef_callback_t, cef_request_t, cef_server_create, cef_server_handler_t, cef_server_t,
+    cef_string_t,
+};
+use std::{collections::HashMap, ffi::c_void, os::raw::c_int, slice};
+
+ref_counted_ptr! {
+    /// Structure representing a server that supports HTTP and WebSocket requests.
+    /// Server capacity is limited and is intended to handle only a small number of
+    /// simultaneous connections (e.g. for communicating between applications on
+    /// localhost). The functions of this structure are safe to call from any thread
+    /// in the browser process unless otherwise indicated.
+    pub struct Server(*mut cef_server_t);
+}
+
+impl Server {
+    /// Create a new server that binds to `address` and `port`. `address` must be a
+    /// valid IPv4 or IPv6 address (e.g. 127.0.0.1 or ::1) and `port` must be a port
+    /// number outside of the reserved range (e.g. between 1025 and 65535 on most
+    /// platforms), or 0 to bind to any free port, which [Server::get_address]
+    /// includes once the server is running. `backlog` is the maximum number of
+    /// pending connections. A new thread will be created for each call (the
+    /// "dedicated server thread"), so use a separate `handler` for each server.
+    /// [ServerHandlerCallbacks::on_server_created] will be called on the dedicated
+    /// server thread to report success or failure. See its documentation for a
+    /// description of the server lifespan.
+    pub fn create(address: &str, port: u16, backlog: i32, handler: impl ServerHandlerCallbacks) {
+        unsafe {
+            cef_server_create(
+                CefString::new(address).as_ptr(),
+                port,
+                backlog,
+                ServerHandlerWrapper(Box::new(handler)).wrap().into_raw(),
+            );
+        }
+    }
+    /// Returns the task runner for the dedicated server thread.
+    pub fn get_task_runner(&self) -> Option<TaskRunner> {
+        self.0
+            .get_task_runner
+            .and_then(|get_task_runner| unsafe { TaskRunner::from_ptr(get_task_runner(self.as_ptr())) })
+    }
+    /// Stop the server and shut down the dedicated server thread. See
+    /// [ServerHandlerCallbacks::on_server_created] documentation for a description
+    /// of the server lifespan.
+    pub fn shutdown(&self) {
+        if let Some(shutdown) = self.0.shutdown {
+            unsafe { shutdown(self.as_ptr()) }
+        }
+    }
+    /// Returns true if the server is currently running and accepting incoming
+    /// connections. See [ServerHandlerCallbacks::on_server_created] documentation
+    /// for a description of the server lifespan. This function must be called on
+    /// the dedicated server thread.
+    pub fn is_running(&self) -> bool {
+        self.0
+            .is_running
+            .map(|is_running| unsafe { is_running(self.as_ptr()) != 0 })
+            .unwrap_or(false)
+    }
+    /// Returns the server address including the port number.
+    pub fn get_address(&self) -> String {
+        self.0
+            .get_address
+            .and_then(|get_address| unsafe { CefString::from_userfree(get_address(self.as_ptr())) })
+            .map(String::from)
+            .unwrap_or_default()
+    }
+    /// Returns true if the server currently has a connection. This function must
+    /// be called on the dedicated server thread.
+    pub fn has_connection(&self) -> bool {
+        self.0
+            .has_connection
+            .map(|has_connection| unsafe { has_connection(self.as_ptr()) != 0 })
+            .unwrap_or(false)
+    }
+    /// Returns true if `connection_id` represents a valid connection. This
+    /// function must be called on the dedicated server thread.
+    pub fn is_valid_connection(&self, connection_id: i32) -> bool {
+        self.0
+            .is_valid_connection
+            .map(|is_valid_connection| unsafe { is_valid_connection(self.as_ptr(), connection_id) != 0 })
+            .unwrap_or(false)
+    }
+    /// Send an HTTP 200 "OK" response to the connection identified by
+    /// `connection_id`. `content_type` is the response content type (e.g.
+    /// "text/html") and `data` is the response content. The connection will be
+    /// closed automatically after the response is sent.
+    pub fn send_http_200_response(&self, connection_id: i32, content_type: &str, data: &[u8]) {
+        if let Some(send_http200response) = self.0.send_http200response {
+            unsafe {
+                send_http200response(
+                    self.as_ptr(),
+                    connection_id,
+                    CefString::new(content_type).as_ptr(),
+                    data.as_ptr() as *const c_void,
+                    data.len(),
+                );
+            }
+        }
+    }
+    /// Send an HTTP 404 "Not Found" response to the connection identified by
+    /// `connection_id`. The connection will be closed automatically after the
+    /// response is sent.
+    pub fn send_http_404_response(&self, connection_id: i32) {
+        if let Some(send_http404response) = self.0.send_http404response {
+            unsafe { send_http404response(self.as_ptr(), connection_id) }
+        }
+    }
+    /// Send an HTTP 500 "Internal Server Error" response to the connection
+    /// identified by `connection_id`. `error_message` is the associated error
+    /// message. The connection will be closed automatically after the response is
+    /// sent.
+    pub fn send_http_500_response(&self, connection_id: i32, error_message: &str) {
+        if let Some(send_http500response) = self.0.send_http500response {
+            unsafe {
+                send_http500response(self.as_ptr(), connection_id, CefString::new(error_message).as_ptr());
+            }
+        }
+    }
+    /// Send a custom HTTP response to the connection identified by
+    /// `connection_id`. `response_code` is the HTTP response code sent in the
+    /// status line (e.g. 200), `content_type` is the response content type sent
+    /// as the "Content-Type" header (e.g. "text/html"), `content_length` is the
+    /// expected content length sent as the "Content-Length" header if it's known,
+    /// and `extra_headers` is the map of extra response headers. Send the content
+    /// with [Server::send_raw_data] afterwards, and close the connection with
+    /// [Server::close_connection] once it's done if `content_length` isn't known.
+    pub fn send_http_response(
+        &self,
+        connection_id: i32,
+        response_code: i32,
+        content_type: &str,
+        content_length: Option<u64>,
+        extra_headers: &HashMap<String, Vec<String>>,
+    ) {
+        if let Some(send_http_response) = self.0.send_http_response {
+            let extra_headers = MultiMap::from(extra_headers);
+            unsafe {
+                send_http_response(
+                    self.as_ptr(),
+                    connection_id,
+                    response_code,
+                    CefString::new(content_type).as_ptr(),
+                    content_length.map(|length| length as i64).unwrap_or(-1),
+                    extra_headers.as_ptr(),
+                );
+            }
+        }
+    }
+    /// Send raw data directly to the connection identified by `connection_id`.
+    /// This function should be used only after [Server::send_http_response] or to
+    /// implement a protocol other than HTTP.
+    pub fn send_raw_data(&self, connection_id: i32, data: &[u8]) {
+        if let Some(send_raw_data) = self.0.send_raw_data {
+            unsafe { send_raw_data(self.as_ptr(), connection_id, data.as_ptr() as *const c_void, data.len()) }
+        }
+    }
+    /// Close the connection identified by `connection_id`. See
+    /// [Server::send_http_response] documentation for intended usage.
+    pub fn close_connection(&self, connection_id: i32) {
+        if let Some(close_connection) = self.0.close_connection {
+            unsafe { close_connection(self.as_ptr(), connection_id) }
+        }
+    }
+    /// Send a WebSocket message to the connection identified by `connection_id`.
+    /// `data` is the message content. Only connections accepted with
+    /// [WebSocketRequestCallback::accept] can receive messages.
+    pub fn send_web_socket_message(&self, connection_id: i32, data: &[u8]) {
+        if let Some(send_web_socket_message) = self.0.send_web_socket_message {
+            unsafe {
+                send_web_socket_message(self.as_ptr(), connection_id, data.as_ptr() as *const c_void, data.len())
+            }
+        }
+    }
+}
+
+/// Accepts or declines a WebSocket request passed to
+/// [ServerHandlerCallbacks::on_web_socket_request].
+pub struct WebSocketRequestCallback(Callback);
+
+impl WebSocketRequestCallback {
+    /// Accepts the WebSocket connection.
+    /// [ServerHandlerCallbacks::on_web_socket_connected] will be called once it's
+    /// established.
+    pub fn accept(self) {
+        self.0.cont();
+    }
+    /// Declines the WebSocket connection, which closes it.
+    pub fn decline(self) {
+        self.0.cancel();
+    }
+}
+
+/// Implement this trait to handle HTTP server requests. A new thread will be
+/// created for each [Server::create] call (the "dedicated server thread"), and
+/// the functions of this trait will be called on that thread. It is therefore
+/// recommended to use a different handler for each server.
+pub trait ServerHandlerCallbacks: 'static + Send + Sync {
+    /// Called when `server` is created. If the server was started successfully then
+    /// [Server::is_running] will return true. The server will continue running
+    /// until [Server::shutdown] is called, after which time
+    /// [ServerHandlerCallbacks::on_server_destroyed] will be called. If the server
+    /// failed to start then `on_server_destroyed` will be called immediately after
+    /// this function returns.
+    fn on_server_created(&self, server: Server) {}
+    /// Called when `server` is destroyed. The server thread will be stopped after
+    /// this function returns. The client should release any references to
+    /// `server` when this function is called. See
+    /// [ServerHandlerCallbacks::on_server_created] documentation for a description
+    /// of server lifespan.
+    fn on_server_destroyed(&self, server: Server) {}
+    /// Called when a client connects to `server`. `connection_id` uniquely
+    /// identifies the connection. Each call to this function will have a matching
+    /// call to [ServerHandlerCallbacks::on_client_disconnected].
+    fn on_client_connected(&self, server: Server, connection_id: i32) {}
+    /// Called when a client disconnects from `server`. `connection_id` uniquely
+    /// identifies the connection. The client should release any data associated
+    /// with `connection_id` when this function is called and `connection_id`
+    /// should no longer be passed to [Server] functions. Disconnects can originate
+    /// from either the client or the server.
+    fn on_client_disconnected(&self, server: Server, connection_id: i32) {}
+    /// Called when `server` receives an HTTP request. `connection_id` uniquely
+    /// identifies the connection, `client_address` is the requesting IPv4 or IPv6
+    /// client address including port number, and `request` contains the request
+    /// contents (URL, function, headers and optional POST data). Call [Server]
+    /// functions either synchronously or asynchronously to send a response. The
+    /// default implementation responds with a 404.
+    fn on_http_request(&self, server: Server, connection_id: i32, client_address: &str, request: Request) {
+        server.send_http_404_response(connection_id);
+    }
+    /// Called when `server` receives a WebSocket request. `connection_id` uniquely
+    /// identifies the connection, `client_address` is the requesting IPv4 or IPv6
+    /// client address including port number, and `request` contains the request
+    /// contents (URL, function, headers and optional POST data). Accept or decline
+    /// the request with `callback`, either synchronously or asynchronously. The
+    /// default implementation declines all WebSocket requests.
+    fn on_web_socket_request(
+        &self,
+        server: Server,
+        connection_id: i32,
+        client_address: &str,
+        request: Request,
+        callback: WebSocketRequestCallback,
+    ) {
+        callback.decline();
+    }
+    /// Called after the client has accepted the WebSocket connection for `server`
+    /// and `connection_id` via [ServerHandlerCallbacks::on_web_socket_request].
+    /// Call [Server::send_web_socket_message] to send messages to the client.
+    fn on_web_socket_connected(&self, server: Server, connection_id: i32) {}
+    /// Called when `server` receives a WebSocket message. `connection_id` uniquely
+    /// identifies the connection and `data` is the message content. Call
+    /// [Server::send_web_socket_message] to send messages to the client.
+    fn on_web_socket_message(&self, server: Server, connection_id: i32, data: &[u8]) {}
+}
+
+struct ServerHandlerWrapper(Box<dyn ServerHandlerCallbacks>);
+
+impl Wrapper for ServerHandlerWrapper {
+    type Cef = cef_server_handler_t;
+    fn wrap(self) -> RefCountedPtr<Self::Cef> {
+        RefCountedPtr::wrap(
+            cef_server_handler_t {
+                base: unsafe { std::mem::zeroed() },
+                on_server_created: Some(Self::server_created),
+                on_server_destroyed: Some(Self::server_destroyed),
+                on_client_connected: Some(Self::client_connected),
+                on_client_disconnected: Some(Self::client_disconnected),
+                on_http_request: Some(Self::http_request),
+                on_web_socket_request: Some(Self::web_socket_request),
+                on_web_socket_connected: Some(Self::web_socket_connected),
+                on_web_socket_message: Some(Self::web_socket_message),
+            },
+            self,
+        )
+    }
+}
+
+cef_callback_impl! {
+    impl for ServerHandlerWrapper: cef_server_handler_t {
+        fn server_created(&self, server: Server: *mut cef_server_t) {
+            self.0.on_server_created(server);
+        }
+        fn server_destroyed(&self, server: Server: *mut cef_server_t) {
+            self.0.on_server_destroyed(server);
+        }
+        fn client_connected(&self, server: Server: *mut cef_server_t, connection_id: i32: c_int) {
+            self.0.on_client_connected(server, connection_id);
+        }
+        fn client_disconnected(&self, server: Server: *mut cef_server_t, connection_id: i32: c_int) {
+            self.0.on_client_disconnected(server, connection_id);
+        }
+        fn http_request(
+            &self,
+            server: Server: *mut cef_server_t,
+            connection_id: i32: c_int,
+            client_address: &CefString: *const cef_string_t,
+            request: Request: *mut cef_request_t,
+        ) {
+            self.0.on_http_request(server, connection_id, &String::from(client_address), request);
+        }
+        fn web_socket_request(
+            &self,
+            server: Server: *mut cef_server_t,
+            connection_id: i32: c_int,
+            client_address: &CefString: *const cef_string_t,
+            request: Request: *mut cef_request_t,
+            callback: Callback: *mut cef_callback_t,
+        ) {
+            self.0.on_web_socket_request(
+                server,
+                connection_id,
+                &String::from(client_address),
+                request,
+                WebSocketRequestCallback(callback),
+            );
+        }
+        fn web_socket_connected(&self, server: Server: *mut cef_server_t, connection_id: i32: c_int) {
+            self.0.on_web_socket_connected(server, connection_id);
+        }
+        fn web_socket_message(
+            &self,
+            server: Server: *mut cef_server_t,
+            connection_id: i32: c_int,
+            data: *const c_void: *const c_void,
+            data_size: usize: usize,
+        ) {
+            let data = if data.is_null() {
+                &[][..]
+            } else {
+                unsafe { slice::from_raw_parts(data as *const u8, data_size) }
+            };
+            self.0.on_web_socket_message(server, connection_id, data);
+        }
+    }
+}
@@ -1,20 +1,343 @@
 use cef_sys::{
     _cef_stream_reader_t,
     _cef_stream_writer_t,
+    cef_read_handler_t,
+    cef_write_handler_t,
+    cef_stream_reader_create_for_file,
+    cef_stream_reader_create_for_handler,
+    cef_stream_writer_create_for_file,
+    cef_stream_writer_create_for_handler,
+};
+use crate::{
+    refcounted::{RefCountedPtr, Wrapper},
+    string::CefString,
+};
+use parking_lot::Mutex;
+use std::{
+    ffi::c_void,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    os::raw::c_int,
+    path::Path,
+    slice,
 };
-use std::io::{self, SeekFrom};
 
 ref_counted_ptr!{
+    /// Structure used to read data from a stream. The functions of this structure
+    /// may be called on any thread.
     pub struct StreamReader(*mut _cef_stream_reader_t);
 }
 
 ref_counted_ptr!{
+    /// Structure used to write data to a stream. The functions of this structure
+    /// may be called on any thread.
     pub struct StreamWriter(*mut _cef_stream_writer_t);
 }
 
-// TODO: CREATE CUSTOM READERS AND WRITERS
+ref_counted_ptr!{
+    /// Structure the client can implement to provide a custom stream reader. The
+    /// functions of this structure may be called on any thread.
+    pub struct ReadHandler(*mut cef_read_handler_t);
+}
+
+ref_counted_ptr!{
+    /// Structure the client can implement to provide a custom stream writer. The
+    /// functions of this structure may be called on any thread.
+    pub struct WriteHandler(*mut cef_write_handler_t);
+}
+
+impl ReadHandler {
+    pub fn new<C: ReadHandlerCallbacks>(callbacks: C) -> ReadHandler {
+        unsafe{ ReadHandler::from_ptr_unchecked(ReadHandlerWrapper(Mutex::new(Box::new(callbacks))).wrap().into_raw()) }
+    }
+}
+
+impl WriteHandler {
+    pub fn new<C: WriteHandlerCallbacks>(callbacks: C) -> WriteHandler {
+        unsafe{ WriteHandler::from_ptr_unchecked(WriteHandlerWrapper(Mutex::new(Box::new(callbacks))).wrap().into_raw()) }
+    }
+}
+
+pub trait ReadHandlerCallbacks: 'static + Send {
+    /// Read raw binary data into `data`, returning how many bytes were read.
+    fn read(&mut self, data: &mut [u8]) -> usize;
+    /// Seek to the specified position.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<()>;
+    /// Return the current offset position.
+    fn tell(&mut self) -> u64;
+    /// Return `true` if at end of file.
+    fn eof(&mut self) -> bool;
+    /// Return `true` if this handler performs work like accessing the file
+    /// system which may block. Used as a hint for determining the thread to
+    /// access the handler from.
+    fn may_block(&mut self) -> bool;
+}
+
+pub trait WriteHandlerCallbacks: 'static + Send {
+    /// Write raw binary data from `data`, returning how many bytes were written.
+    fn write(&mut self, data: &[u8]) -> usize;
+    /// Seek to the specified position.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<()>;
+    /// Return the current offset position.
+    fn tell(&mut self) -> u64;
+    /// Flush the stream.
+    fn flush(&mut self) -> io::Result<()>;
+    /// Return `true` if this handler performs work like accessing the file
+    /// system which may block. Used as a hint for determining the thread to
+    /// access the handler from.
+    fn may_block(&mut self) -> bool;
+}
+
+/// Converts the arguments of a C `seek` call.
+fn seek_from(offset: i64, whence: c_int) -> Option<SeekFrom> {
+    match whence {
+        libc::SEEK_SET if offset >= 0 => Some(SeekFrom::Start(offset as u64)),
+        libc::SEEK_CUR => Some(SeekFrom::Current(offset)),
+        libc::SEEK_END => Some(SeekFrom::End(offset)),
+        _ => None,
+    }
+}
+
+/// Converts the arguments of a [SeekFrom] to a C `seek` call.
+fn whence_offset(pos: SeekFrom) -> (c_int, i64) {
+    match pos {
+        SeekFrom::Start(o) => (libc::SEEK_SET, o as i64),
+        SeekFrom::End(o) => (libc::SEEK_END, o),
+        SeekFrom::Current(o) => (libc::SEEK_CUR, o),
+    }
+}
+
+struct ReadHandlerWrapper(Mutex<Box<dyn ReadHandlerCallbacks>>);
+
+impl Wrapper for ReadHandlerWrapper {
+    type Cef = cef_read_handler_t;
+    fn wrap(self) -> RefCountedPtr<Self::Cef> {
+        RefCountedPtr::wrap(
+            cef_read_handler_t {
+                base: unsafe { std::mem::zeroed() },
+                read: Some(Self::read),
+                seek: Some(Self::seek),
+                tell: Some(Self::tell),
+                eof: Some(Self::eof),
+                may_block: Some(Self::may_block),
+            },
+            self,
+        )
+    }
+}
+
+cef_callback_impl!{
+    impl for ReadHandlerWrapper: cef_read_handler_t {
+        fn read(
+            &self,
+            ptr: *mut c_void: *mut c_void,
+            size: usize: usize,
+            n: usize: usize,
+        ) -> usize {
+            if ptr.is_null() || size == 0 {
+                return 0;
+            }
+            let data = unsafe{ slice::from_raw_parts_mut(ptr as *mut u8, size * n) };
+            let mut handler = self.0.lock();
+            // Only complete items count, so keep reading until the last one is full.
+            let mut read = 0;
+            while read < data.len() {
+                match handler.read(&mut data[read..]) {
+                    0 => break,
+                    count => read += count,
+                }
+            }
+            read / size
+        }
+        fn seek(
+            &self,
+            offset: i64: i64,
+            whence: c_int: c_int,
+        ) -> c_int {
+            match seek_from(offset, whence).map(|pos| self.0.lock().seek(pos)) {
+                Some(Ok(())) => 0,
+                _ => -1,
+            }
+        }
+        fn tell(&self) -> i64 {
+            self.0.lock().tell() as i64
+        }
+        fn eof(&self) -> c_int {
+            self.0.lock().eof() as c_int
+        }
+        fn may_block(&self) -> c_int {
+            self.0.lock().may_block() as c_int
+        }
+    }
+}
+
+struct WriteHandlerWrapper(Mutex<Box<dyn WriteHandlerCallbacks>>);
+
+impl Wrapper for WriteHandlerWrapper {
+    type Cef = cef_write_handler_t;
+    fn wrap(self) -> RefCountedPtr<Self::Cef> {
+        RefCountedPtr::wrap(
+            cef_write_handler_t {
+                base: unsafe { std::mem::zeroed() },
+                write: Some(Self::write),
+                seek: Some(Self::seek),
+                tell: Some(Self::tell),
+                flush: Some(Self::flush),
+                may_block: Some(Self::may_block),
+            },
+            self,
+        )
+    }
+}
+
+cef_callback_impl!{
+    impl for WriteHandlerWrapper: cef_write_handler_t {
+        fn write(
+            &self,
+            ptr: *const c_void: *const c_void,
+            size: usize: usize,
+            n: usize: usize,
+        ) -> usize {
+            if ptr.is_null() || size == 0 {
+                return 0;
+            }
+            let data = unsafe{ slice::from_raw_parts(ptr as *const u8, size * n) };
+            let mut handler = self.0.lock();
+            let mut written = 0;
+            while written < data.len() {
+                match handler.write(&data[written..]) {
+                    0 => break,
+                    count => written += count,
+                }
+            }
+            written / size
+        }
+        fn seek(
+            &self,
+            offset: i64: i64,
+            whence: c_int: c_int,
+        ) -> c_int {
+            match seek_from(offset, whence).map(|pos| self.0.lock().seek(pos)) {
+                Some(Ok(())) => 0,
+                _ => -1,
+            }
+        }
+        fn tell(&self) -> i64 {
+            self.0.lock().tell() as i64
+        }
+        fn flush(&self) -> c_int {
+            match self.0.lock().flush() {
+                Ok(()) => 0,
+                Err(_) => -1,
+            }
+        }
+        fn may_block(&self) -> c_int {
+            self.0.lock().may_block() as c_int
+        }
+    }
+}
+
+/// Adapts a [Read] + [Seek] implementation to [ReadHandlerCallbacks].
+struct IoReadHandler<R> {
+    reader: R,
+    eof: bool,
+    may_block: bool,
+}
+
+impl<R: Read + Seek + Send + 'static> ReadHandlerCallbacks for IoReadHandler<R> {
+    fn read(&mut self, data: &mut [u8]) -> usize {
+        loop {
+            match self.reader.read(data) {
+                Ok(0) if !data.is_empty() => {
+                    self.eof = true;
+                    return 0;
+                }
+                Ok(count) => return count,
+                Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    log::error!("error reading stream: {}", error);
+                    return 0;
+                }
+            }
+        }
+    }
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<()> {
+        self.reader.seek(pos)?;
+        self.eof = false;
+        Ok(())
+    }
+    fn tell(&mut self) -> u64 {
+        self.reader.seek(SeekFrom::Current(0)).unwrap_or(0)
+    }
+    fn eof(&mut self) -> bool {
+        self.eof
+    }
+    fn may_block(&mut self) -> bool {
+        self.may_block
+    }
+}
+
+/// Adapts a [Write] + [Seek] implementation to [WriteHandlerCallbacks].
+struct IoWriteHandler<W> {
+    writer: W,
+}
+
+impl<W: Write + Seek + Send + 'static> WriteHandlerCallbacks for IoWriteHandler<W> {
+    fn write(&mut self, data: &[u8]) -> usize {
+        loop {
+            match self.writer.write(data) {
+                Ok(count) => return count,
+                Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    log::error!("error writing stream: {}", error);
+                    return 0;
+                }
+            }
+        }
+    }
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<()> {
+        self.writer.seek(pos).map(|_| ())
+    }
+    fn tell(&mut self) -> u64 {
+        self.writer.seek(SeekFrom::Current(0)).unwrap_or(0)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+    fn may_block(&mut self) -> bool {
+        true
+    }
+}
 
 impl StreamReader {
+    /// Create a new stream reader object from a file. Returns None if the file
+    /// can't be opened.
+    pub fn from_file(path: impl AsRef<Path>) -> Option<StreamReader> {
+        let path = CefString::new(&path.as_ref().to_string_lossy());
+        unsafe{ StreamReader::from_ptr(cef_stream_reader_create_for_file(path.as_ptr())) }
+    }
+    /// Create a new stream reader object that reads from an in-memory buffer.
+    pub fn from_bytes(data: Vec<u8>) -> StreamReader {
+        StreamReader::from_handler(ReadHandler::new(IoReadHandler {
+            reader: Cursor::new(data),
+            eof: false,
+            may_block: false,
+        }))
+    }
+    /// Create a new stream reader object that reads from `reader`. The reader is
+    /// assumed to block, so CEF avoids calling it on threads where blocking is
+    /// not allowed.
+    pub fn from_reader(reader: impl Read + Seek + Send + 'static) -> StreamReader {
+        StreamReader::from_handler(ReadHandler::new(IoReadHandler {
+            reader,
+            eof: false,
+            may_block: true,
+        }))
+    }
+    /// Create a new stream reader object from a custom handler.
+    pub fn from_handler(handler: ReadHandler) -> StreamReader {
+        unsafe{ StreamReader::from_ptr_unchecked(cef_stream_reader_create_for_handler(handler.into_raw())) }
+    }
+
     /// Read raw binary data, returning how many bytes were read.
     pub fn read(&self, data: &mut [u8]) -> usize {
         unsafe{ (self.0.read.unwrap())(self.as_ptr(), data.as_mut_ptr() as *mut _, 1, data.len()) }
@@ -22,11 +345,7 @@ impl StreamReader {
 
     /// Seek to the specified position.
     pub fn seek(&self, whence: SeekFrom) -> io::Result<()> {
-        let (whence, offset) = match whence {
-            SeekFrom::Start(o) => (libc::SEEK_SET, o as i64),
-            SeekFrom::End(o) => (libc::SEEK_END, o),
-            SeekFrom::Current(o) => (libc::SEEK_CUR, o),
-        };
+        let (whence, offset) = whence_offset(whence);
         let result = unsafe{ (self.0.seek.unwrap())(self.as_ptr(), offset, whence) };
         match result {
             0 => Ok(()),
@@ -53,6 +372,21 @@ impl StreamReader {
 }
 
 impl StreamWriter {
+    /// Create a new stream writer object for a file. Returns None if the file
+    /// can't be opened.
+    pub fn from_file(path: impl AsRef<Path>) -> Option<StreamWriter> {
+        let path = CefString::new(&path.as_ref().to_string_lossy());
+        unsafe{ StreamWriter::from_ptr(cef_stream_writer_create_for_file(path.as_ptr())) }
+    }
+    /// Create a new stream writer object that writes to `writer`.
+    pub fn from_writer(writer: impl Write + Seek + Send + 'static) -> StreamWriter {
+        StreamWriter::from_handler(WriteHandler::new(IoWriteHandler { writer }))
+    }
+    /// Create a new stream writer object from a custom handler.
+    pub fn from_handler(handler: WriteHandler) -> StreamWriter {
+        unsafe{ StreamWriter::from_ptr_unchecked(cef_stream_writer_create_for_handler(handler.into_raw())) }
+    }
+
     /// Write raw binary data, returning how many bytes were written.
     pub fn write(&self, data: &[u8]) -> usize {
         unsafe{ (self.0.write.unwrap())(self.as_ptr(), data.as_ptr() as *mut _, 1, data.len()) }
@@ -60,11 +394,7 @@ impl StreamWriter {
 
     /// Seek to the specified position.
     pub fn seek(&self, whence: SeekFrom) -> io::Result<()> {
-        let (whence, offset) = match whence {
-            SeekFrom::Start(o) => (libc::SEEK_SET, o as i64),
-            SeekFrom::End(o) => (libc::SEEK_END, o),
-            SeekFrom::Current(o) => (libc::SEEK_CUR, o),
-        };
+        let (whence, offset) = whence_offset(whence);
         let result = unsafe{ (self.0.seek.unwrap())(self.as_ptr(), offset, whence) };
         match result {
             0 => Ok(()),
@@ -77,7 +407,7 @@ impl StreamWriter {
         unsafe{ (self.0.tell.unwrap())(self.as_ptr()) as usize }
     }
 
-    /// Returns `true` if the writer is at the end of the file.
+    /// Flush the stream.
     pub fn flush(&self) -> io::Result<()> {
         let result = unsafe{ (self.0.flush.unwrap())(self.as_ptr()) };
         match result {
@@ -93,3 +423,32 @@ impl StreamWriter {
         unsafe{ (self.0.may_block.unwrap())(self.as_ptr()) != 0 }
     }
 }
+
+impl Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(StreamReader::read(self, buf))
+    }
+}
+
+impl Seek for StreamReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        StreamReader::seek(self, pos)?;
+        Ok(self.tell() as u64)
+    }
+}
+
+impl Write for StreamWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(StreamWriter::write(self, buf))
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        StreamWriter::flush(self)
+    }
+}
+
+impl Seek for StreamWriter {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        StreamWriter::seek(self, pos)?;
+        Ok(self.tell() as u64)
+    }
+}
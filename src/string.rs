@@ -178,12 +178,16 @@ impl From<CefString> for String {
 
 impl<'a> From<&'a mut CefString> for String {
     fn from(cef: &'a mut CefString) -> String {
-        String::from_utf16_lossy(unsafe { std::slice::from_raw_parts(cef.0.str_, cef.0.length) })
+        String::from(&*cef)
     }
 }
 
 impl<'a> From<&'a CefString> for String {
     fn from(cef: &'a CefString) -> String {
+        // Strings CEF left unset have a null buffer.
+        if cef.0.str_.is_null() {
+            return String::new();
+        }
         String::from_utf16_lossy(unsafe { std::slice::from_raw_parts(cef.0.str_, cef.0.length) })
     }
 }
@@ -0,0 +1,190 @@
+//! Dedicated threads that run CEF tasks, and an event to synchronize threads
+//! with.
+//!
+//! ```ignore
+//! let worker = CefThread::new("indexer", ThreadPriority::Background, MessageLoopType::Default, ComInitMode::None).unwrap();
+//! let done = WaitableEvent::new(false, false);
+//! let signal = done.clone();
+//! worker.get_task_runner().unwrap().post_task(move || {
+//!     // ... long running work ...
+//!     signal.signal();
+//! });
+//! // On another thread that may block:
+//! done.wait();
+//! worker.stop();
+//! ```
+
+use crate::{string::CefString, task::TaskRunner};
+use cef_sys::{
+    cef_com_init_mode_t, cef_message_loop_type_t, cef_platform_thread_id_t, cef_thread_create,
+    cef_thread_priority_t, cef_thread_t, cef_waitable_event_create, cef_waitable_event_t,
+};
+use std::{marker::PhantomData, time::Duration};
+
+/// Thread priority values listed in increasing order of importance.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ThreadPriority {
+    /// Suitable for threads that shouldn't disrupt high priority work.
+    Background = cef_thread_priority_t::TP_BACKGROUND as isize,
+    /// Default priority level.
+    Normal = cef_thread_priority_t::TP_NORMAL as isize,
+    /// Suitable for threads which generate data for the display (at ~60Hz).
+    Display = cef_thread_priority_t::TP_DISPLAY as isize,
+    /// Suitable for low-latency, glitch-resistant audio.
+    RealtimeAudio = cef_thread_priority_t::TP_REALTIME_AUDIO as isize,
+}
+
+/// Message loop types. Indicates the set of asynchronous events that a message
+/// loop can process.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MessageLoopType {
+    /// Supports tasks and timers.
+    Default = cef_message_loop_type_t::ML_TYPE_DEFAULT as isize,
+    /// Supports tasks, timers and native UI events (e.g. Windows messages).
+    UI = cef_message_loop_type_t::ML_TYPE_UI as isize,
+    /// Supports tasks, timers and asynchronous IO events.
+    IO = cef_message_loop_type_t::ML_TYPE_IO as isize,
+}
+
+/// Windows COM initialization mode. Specifies how COM will be initialized for a
+/// new thread. Ignored on other platforms.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ComInitMode {
+    /// No COM initialization.
+    None = cef_com_init_mode_t::COM_INIT_MODE_NONE as isize,
+    /// Initialize COM using single-threaded apartments.
+    STA = cef_com_init_mode_t::COM_INIT_MODE_STA as isize,
+    /// Initialize COM using multi-threaded apartments.
+    MTA = cef_com_init_mode_t::COM_INIT_MODE_MTA as isize,
+}
+
+/// The platform's native thread identifier.
+pub type PlatformThreadId = cef_platform_thread_id_t;
+
+ref_counted_ptr! {
+    struct CefThreadPtr(*mut cef_thread_t);
+}
+
+/// A thread that runs the tasks posted to its [TaskRunner]. The thread is
+/// stopped when [CefThread::stop] is called or the last reference to it is
+/// released.
+///
+/// Stopping the thread joins it, so a `CefThread` must only be used and dropped
+/// on the thread that created it. Other threads can post tasks to it through
+/// [CefThread::get_task_runner].
+#[derive(Clone)]
+pub struct CefThread {
+    thread: CefThreadPtr,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl CefThread {
+    /// Create and start a new thread. This function does not block waiting for
+    /// the thread to run initialization. `display_name` is the name that will be
+    /// used to identify the thread. `priority` is the thread execution priority.
+    /// `message_loop_type` indicates the set of asynchronous events that the
+    /// thread can process. `com_init_mode` specifies how COM will be initialized
+    /// on Windows. Returns None if the thread can't be created.
+    pub fn new(
+        display_name: &str,
+        priority: ThreadPriority,
+        message_loop_type: MessageLoopType,
+        com_init_mode: ComInitMode,
+    ) -> Option<CefThread> {
+        let thread = unsafe {
+            CefThreadPtr::from_ptr(cef_thread_create(
+                CefString::new(display_name).as_ptr(),
+                priority as cef_thread_priority_t::Type,
+                message_loop_type as cef_message_loop_type_t::Type,
+                1,
+                com_init_mode as cef_com_init_mode_t::Type,
+            ))
+        }?;
+        Some(CefThread {
+            thread,
+            _not_send: PhantomData,
+        })
+    }
+    /// Returns the task runner that will execute code on this thread's message
+    /// loop. This function is safe to call from any thread.
+    pub fn get_task_runner(&self) -> Option<TaskRunner> {
+        self.thread
+            .0
+            .get_task_runner
+            .and_then(|get_task_runner| unsafe { TaskRunner::from_ptr(get_task_runner(self.thread.as_ptr())) })
+    }
+    /// Returns the platform thread ID. It will only be valid after [CefThread::new]
+    /// has returned. This function is safe to call from any thread.
+    pub fn get_platform_thread_id(&self) -> PlatformThreadId {
+        unsafe { self.thread.0.get_platform_thread_id.unwrap()(self.thread.as_ptr()) }
+    }
+    /// Stop and join the thread. This function must be called from the same
+    /// thread that called [CefThread::new].
+    pub fn stop(&self) {
+        if let Some(stop) = self.thread.0.stop {
+            unsafe { stop(self.thread.as_ptr()) }
+        }
+    }
+    /// Returns true if the thread is currently running. This function must be
+    /// called from the same thread that called [CefThread::new].
+    pub fn is_running(&self) -> bool {
+        self.thread
+            .0
+            .is_running
+            .map(|is_running| unsafe { is_running(self.thread.as_ptr()) != 0 })
+            .unwrap_or(false)
+    }
+}
+
+ref_counted_ptr! {
+    /// An event that one or more threads can wait on until another thread signals
+    /// it. The functions of this structure are safe to call from any thread, but
+    /// waiting is not allowed on the browser process UI or IO threads.
+    pub struct WaitableEvent(*mut cef_waitable_event_t);
+}
+
+impl WaitableEvent {
+    /// Create a new waitable event. If `automatic_reset` is true then the event
+    /// state is automatically reset to un-signaled after a single waiting thread
+    /// has been released; otherwise, the state remains signaled until
+    /// [WaitableEvent::reset] is called manually. If `initially_signaled` is true
+    /// then the event will start in the signaled state.
+    pub fn new(automatic_reset: bool, initially_signaled: bool) -> WaitableEvent {
+        unsafe {
+            WaitableEvent::from_ptr_unchecked(cef_waitable_event_create(
+                automatic_reset as _,
+                initially_signaled as _,
+            ))
+        }
+    }
+    /// Put the event in the un-signaled state.
+    pub fn reset(&self) {
+        unsafe { self.0.reset.unwrap()(self.as_ptr()) }
+    }
+    /// Put the event in the signaled state. This causes any thread blocked on
+    /// [WaitableEvent::wait] to be woken up.
+    pub fn signal(&self) {
+        unsafe { self.0.signal.unwrap()(self.as_ptr()) }
+    }
+    /// Returns true if the event is in the signaled state, else false. If the
+    /// event was created with `automatic_reset` set to true then calling this
+    /// function will also cause a reset.
+    pub fn is_signaled(&self) -> bool {
+        unsafe { self.0.is_signaled.unwrap()(self.as_ptr()) != 0 }
+    }
+    /// Wait indefinitely for the event to be signaled. This function will not
+    /// return until after the call to [WaitableEvent::signal] has completed.
+    pub fn wait(&self) {
+        unsafe { self.0.wait.unwrap()(self.as_ptr()) }
+    }
+    /// Wait up to `max_duration` for the event to be signaled. Returns true if
+    /// the event was signaled. A return value of false does not necessarily mean
+    /// that `max_duration` was exceeded.
+    pub fn timed_wait(&self, max_duration: Duration) -> bool {
+        let max_ms = max_duration.as_millis().min(i64::MAX as u128) as i64;
+        unsafe { self.0.timed_wait.unwrap()(self.as_ptr(), max_ms) != 0 }
+    }
+}
@@ -26,6 +26,13 @@ use crate::{
     string::CefString,
 };
 
+mod range;
+pub use self::range::RangeResourceHandler;
+mod streaming;
+pub use self::streaming::{ServerSentEvent, StreamResponse};
+mod text_rewrite;
+pub use self::text_rewrite::TextRewriteFilter;
+
 /// Flags that represent [URLRequest] status.
 #[repr(C)]
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -0,0 +1,500 @@
+//! A [ResourceHandlerCallbacks] implementation that answers HTTP Range requests,
+//! which `<video>` and `<audio>` elements need for seeking.
+
+use super::{ResourceHandlerCallbacks, ResourceReadHandler, ResourceSkipCallback};
+use crate::{callback::Callback, load_handler::ErrorCode, request::Request, response::Response};
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Requests with more ranges than this are answered with the whole resource.
+const MAX_RANGES: usize = 64;
+
+/// A part of the response body.
+enum Segment {
+    Bytes(Vec<u8>),
+    Source { start: u64, len: u64 },
+}
+
+impl Segment {
+    fn len(&self) -> u64 {
+        match self {
+            Segment::Bytes(bytes) => bytes.len() as u64,
+            Segment::Source { len, .. } => *len,
+        }
+    }
+}
+
+struct Plan {
+    status: i32,
+    status_text: &'static str,
+    mime_type: String,
+    headers: Vec<(&'static str, String)>,
+    segments: Vec<Segment>,
+    /// The current segment, and the offset into it.
+    segment: usize,
+    offset: u64,
+    /// Whether the request had a `Range` header, which the plan answers.
+    range_requested: bool,
+    /// Whether reading has started.
+    started: bool,
+}
+
+impl Plan {
+    fn len(&self) -> u64 {
+        self.segments.iter().map(Segment::len).sum()
+    }
+    /// Moves forward by up to `count` bytes, returning how many bytes were
+    /// skipped.
+    fn advance(&mut self, mut count: u64) -> u64 {
+        let mut advanced = 0;
+        while count > 0 && self.segment < self.segments.len() {
+            let available = self.segments[self.segment].len() - self.offset;
+            let step = available.min(count);
+            self.offset += step;
+            advanced += step;
+            count -= step;
+            if self.offset == self.segments[self.segment].len() {
+                self.segment += 1;
+                self.offset = 0;
+            }
+        }
+        advanced
+    }
+    /// Handles a request from CEF to skip `count` bytes, returning how many
+    /// bytes were skipped.
+    ///
+    /// CEF only skips to apply the `Range` header of the request itself, before
+    /// the first read (see [ResourceHandlerCallbacks::skip]). The request is
+    /// read-only by the time a handler sees it, so the header can't be removed.
+    /// As the plan already answers the header, with a 206, 416 or a whole 200
+    /// response, these skips are reported as done without moving. Any other
+    /// skip moves forward.
+    fn skip(&mut self, count: u64) -> u64 {
+        if self.range_requested && !self.started {
+            return count;
+        }
+        self.advance(count)
+    }
+    /// Reads the next part of the body from `source` into `buffer`, returning
+    /// how many bytes were read. Returns an error if `source` failed before
+    /// anything was read.
+    fn read<S: Read + Seek>(&mut self, source: &mut S, buffer: &mut [u8]) -> io::Result<usize> {
+        self.started = true;
+        let mut read = 0;
+        while read < buffer.len() && self.segment < self.segments.len() {
+            let offset = self.offset;
+            let count = match &self.segments[self.segment] {
+                Segment::Bytes(bytes) => {
+                    let available = &bytes[offset as usize..];
+                    let count = available.len().min(buffer.len() - read);
+                    buffer[read..read + count].copy_from_slice(&available[..count]);
+                    count
+                }
+                Segment::Source { start, len } => {
+                    let wanted = (len - offset).min((buffer.len() - read) as u64) as usize;
+                    let result = source.seek(SeekFrom::Start(start + offset))
+                        .and_then(|_| source.read(&mut buffer[read..read + wanted]));
+                    match result {
+                        Ok(0) => {
+                            let error = format!("resource ended {} bytes early", len - offset);
+                            if read == 0 {
+                                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, error));
+                            }
+                            log::error!("{}", error);
+                            break;
+                        }
+                        Ok(count) => count,
+                        Err(error) if read == 0 => return Err(error),
+                        Err(error) => {
+                            log::error!("failed to read resource: {}", error);
+                            break;
+                        }
+                    }
+                }
+            };
+            read += count;
+            self.advance(count as u64);
+        }
+        Ok(read)
+    }
+}
+
+/// Serves a seekable `source` with support for `Range` and `If-Range` request
+/// headers.
+///
+/// A request for a single satisfiable range gets a 206 response with a
+/// `Content-Range` header, a request for several ranges a `multipart/byteranges`
+/// response, and a request whose ranges are all unsatisfiable a 416 response.
+/// Malformed `Range` headers, or ranges whose `If-Range` precondition fails,
+/// get the whole resource with a 200 response.
+///
+/// CEF also reads the `Range` header and asks the handler to skip to its first
+/// byte before reading. Since the handler has already answered the header, it
+/// reports those skips as done without moving, so the range isn't applied
+/// twice.
+///
+/// ```ignore
+/// let file = File::open("movie.mp4")?;
+/// let handler = ResourceHandler::new(RangeResourceHandler::new(file, "video/mp4")?);
+/// ```
+pub struct RangeResourceHandler<S> {
+    source: S,
+    len: u64,
+    mime_type: String,
+    charset: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    headers: Vec<(String, String)>,
+    plan: Option<Plan>,
+}
+
+impl<S: Read + Seek + Send + Sync + 'static> RangeResourceHandler<S> {
+    /// Creates a handler serving `source` with the given MIME type. The length of
+    /// the resource is determined by seeking to the end of `source`.
+    pub fn new(mut source: S, mime_type: &str) -> io::Result<Self> {
+        let len = source.seek(SeekFrom::End(0))?;
+        Ok(RangeResourceHandler {
+            source,
+            len,
+            mime_type: mime_type.to_owned(),
+            charset: String::new(),
+            etag: None,
+            last_modified: None,
+            headers: Vec::new(),
+            plan: None,
+        })
+    }
+    /// Sets the charset of the resource.
+    pub fn charset(mut self, charset: &str) -> Self {
+        self.charset = charset.to_owned();
+        self
+    }
+    /// Sets the `ETag` header. An `If-Range` header naming this entity tag lets
+    /// the range request through.
+    pub fn etag(mut self, etag: &str) -> Self {
+        self.etag = Some(etag.to_owned());
+        self
+    }
+    /// Sets the `Last-Modified` header, as an HTTP date. An `If-Range` header
+    /// with exactly this date lets the range request through.
+    pub fn last_modified(mut self, http_date: &str) -> Self {
+        self.last_modified = Some(http_date.to_owned());
+        self
+    }
+    /// Adds a header to every response.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    fn plan(&self, request: &Request) -> Plan {
+        let headers = request.get_header_map();
+        let header = |name: &str| -> Option<&Vec<String>> {
+            headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, values)| values)
+        };
+        self.plan_for(header("Range").map(Vec::as_slice), header("If-Range").and_then(|values| values.first()))
+    }
+
+    fn plan_for(&self, range: Option<&[String]>, if_range: Option<&String>) -> Plan {
+        let ranges = match range {
+            Some([range]) if self.if_range_matches(if_range) => parse_range(range, self.len),
+            _ => None,
+        };
+        let mut plan = Plan {
+            status: 200,
+            status_text: "OK",
+            mime_type: self.mime_type.clone(),
+            headers: vec![("Accept-Ranges", "bytes".to_owned())],
+            segments: Vec::new(),
+            segment: 0,
+            offset: 0,
+            range_requested: range.is_some(),
+            started: false,
+        };
+        if let Some(etag) = &self.etag {
+            plan.headers.push(("ETag", etag.clone()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            plan.headers.push(("Last-Modified", last_modified.clone()));
+        }
+        match ranges {
+            None => plan.segments.push(Segment::Source { start: 0, len: self.len }),
+            Some(ranges) if ranges.is_empty() => {
+                plan.status = 416;
+                plan.status_text = "Range Not Satisfiable";
+                plan.headers.push(("Content-Range", format!("bytes */{}", self.len)));
+            }
+            Some(ranges) if ranges.len() == 1 => {
+                let (start, end) = ranges[0];
+                plan.status = 206;
+                plan.status_text = "Partial Content";
+                plan.headers.push(("Content-Range", format!("bytes {}-{}/{}", start, end, self.len)));
+                plan.segments.push(Segment::Source { start, len: end - start + 1 });
+            }
+            Some(ranges) => {
+                let boundary = boundary();
+                let content_type = if self.charset.is_empty() {
+                    self.mime_type.clone()
+                } else {
+                    format!("{}; charset={}", self.mime_type, self.charset)
+                };
+                plan.status = 206;
+                plan.status_text = "Partial Content";
+                plan.mime_type = format!("multipart/byteranges; boundary={}", boundary);
+                for (start, end) in ranges {
+                    let part_header = format!(
+                        "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                        boundary, content_type, start, end, self.len,
+                    );
+                    plan.segments.push(Segment::Bytes(part_header.into_bytes()));
+                    plan.segments.push(Segment::Source { start, len: end - start + 1 });
+                }
+                plan.segments.push(Segment::Bytes(format!("\r\n--{}--\r\n", boundary).into_bytes()));
+            }
+        }
+        plan
+    }
+
+    /// Returns true if a range request with the given `If-Range` header may be
+    /// answered with a partial response.
+    fn if_range_matches(&self, if_range: Option<&String>) -> bool {
+        let if_range = match if_range {
+            Some(if_range) => if_range.trim(),
+            None => return true,
+        };
+        if if_range.starts_with('"') || if_range.starts_with("W/") {
+            // If-Range requires the strong comparison, so weak tags never match.
+            match &self.etag {
+                Some(etag) => !etag.starts_with("W/") && etag == if_range,
+                None => false,
+            }
+        } else {
+            self.last_modified.as_deref() == Some(if_range)
+        }
+    }
+}
+
+/// Parses a `Range` header into sorted, merged, inclusive byte ranges within
+/// a resource of length `len`. Returns None if the header is malformed or
+/// should be ignored, and an empty list if no range is satisfiable.
+fn parse_range(header: &str, len: u64) -> Option<Vec<(u64, u64)>> {
+    let equals = header.find('=')?;
+    let (unit, specs) = (&header[..equals], &header[equals + 1..]);
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+    let mut ranges = Vec::new();
+    for spec in specs.split(',').map(str::trim).filter(|spec| !spec.is_empty()) {
+        let dash = spec.find('-')?;
+        let (first, last) = (spec[..dash].trim(), spec[dash + 1..].trim());
+        let range = if first.is_empty() {
+            // A suffix range: the last `suffix` bytes.
+            let suffix: u64 = last.parse().ok()?;
+            if suffix == 0 || len == 0 {
+                None
+            } else {
+                Some((len.saturating_sub(suffix), len - 1))
+            }
+        } else {
+            let first: u64 = first.parse().ok()?;
+            let last = match last {
+                "" => None,
+                last => Some(last.parse::<u64>().ok()?),
+            };
+            if last.map(|last| last < first).unwrap_or(false) {
+                return None;
+            }
+            if first >= len {
+                None
+            } else {
+                Some((first, last.unwrap_or(len - 1).min(len - 1)))
+            }
+        };
+        ranges.extend(range);
+    }
+    if ranges.len() > MAX_RANGES {
+        return None;
+    }
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    Some(merged)
+}
+
+fn boundary() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.subsec_nanos()).unwrap_or(0);
+    format!("{:08x}{:08x}", nanos, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+impl<S: Read + Seek + Send + Sync + 'static> ResourceHandlerCallbacks for RangeResourceHandler<S> {
+    fn open(&mut self, request: Request, handle_request: &mut bool, callback: Callback) -> bool {
+        self.plan = Some(self.plan(&request));
+        *handle_request = true;
+        true
+    }
+    fn get_response_headers(
+        &self,
+        response: Response,
+        response_length: &mut Option<u64>,
+        redirect_url: &mut String,
+    ) {
+        let plan = match &self.plan {
+            Some(plan) => plan,
+            None => {
+                response.set_error(ErrorCode::Failed);
+                return;
+            }
+        };
+        response.set_status(plan.status);
+        response.set_status_text(plan.status_text);
+        response.set_mime_type(&plan.mime_type);
+        if !self.charset.is_empty() && plan.mime_type == self.mime_type {
+            response.set_charset(&self.charset);
+        }
+        for (name, value) in &plan.headers {
+            response.set_header_by_name(name, value, true);
+        }
+        for (name, value) in &self.headers {
+            response.set_header_by_name(name, value, true);
+        }
+        *response_length = Some(plan.len());
+    }
+    fn skip(&mut self, bytes_to_skip: u64, bytes_skipped: &mut u64, callback: ResourceSkipCallback) -> Result<(), ErrorCode> {
+        let plan = self.plan.as_mut().ok_or(ErrorCode::Failed)?;
+        let skipped = plan.skip(bytes_to_skip);
+        if skipped == 0 && bytes_to_skip > 0 {
+            return Err(ErrorCode::RequestRangeNotSatisfiable);
+        }
+        *bytes_skipped = skipped;
+        Ok(())
+    }
+    fn read(&mut self, mut handler: ResourceReadHandler) -> Option<ResourceReadHandler> {
+        let plan = match self.plan.as_mut() {
+            Some(plan) => plan,
+            None => {
+                handler.set_error(ErrorCode::Failed);
+                return Some(handler);
+            }
+        };
+        match plan.read(&mut self.source, handler.as_buffer_ref()) {
+            Ok(read) => handler.set_bytes_read(read as i32),
+            Err(error) => {
+                log::error!("failed to read resource: {}", error);
+                handler.set_error(ErrorCode::Failed);
+            }
+        }
+        Some(handler)
+    }
+    fn cancel(&mut self) {
+        self.plan = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn handler() -> RangeResourceHandler<Cursor<Vec<u8>>> {
+        let data = (0..=255).cycle().take(1000).collect();
+        RangeResourceHandler::new(Cursor::new(data), "video/mp4").unwrap()
+    }
+
+    fn read_all(plan: &mut Plan, source: &mut Cursor<Vec<u8>>) -> Vec<u8> {
+        let mut body = Vec::new();
+        let mut buffer = [0; 7];
+        loop {
+            let read = plan.read(source, &mut buffer).unwrap();
+            if read == 0 {
+                return body;
+            }
+            body.extend_from_slice(&buffer[..read]);
+        }
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_range("bytes=100-199", 1000), Some(vec![(100, 199)]));
+        assert_eq!(parse_range("bytes=900-", 1000), Some(vec![(900, 999)]));
+        assert_eq!(parse_range("bytes=-100", 1000), Some(vec![(900, 999)]));
+        assert_eq!(parse_range("bytes=0-9, 5-19", 1000), Some(vec![(0, 19)]));
+        assert_eq!(parse_range("bytes=1000-", 1000), Some(vec![]));
+        assert_eq!(parse_range("bytes=9-0", 1000), None);
+        assert_eq!(parse_range("items=0-9", 1000), None);
+        assert_eq!(parse_range("bytes", 1000), None);
+    }
+
+    #[test]
+    fn single_range_is_not_skipped_twice() {
+        let mut handler = handler();
+        let mut plan = handler.plan_for(Some(&["bytes=100-199".to_owned()]), None);
+        assert_eq!(plan.status, 206);
+        assert_eq!(plan.len(), 100);
+        // CEF skips to the first byte of the range before reading.
+        assert_eq!(plan.skip(100), 100);
+        let body = read_all(&mut plan, &mut handler.source);
+        assert_eq!(body, handler.source.get_ref()[100..200].to_vec());
+    }
+
+    #[test]
+    fn open_range_is_not_skipped_twice() {
+        let mut handler = handler();
+        let mut plan = handler.plan_for(Some(&["bytes=900-".to_owned()]), None);
+        assert_eq!(plan.status, 206);
+        assert_eq!(plan.skip(900), 900);
+        let body = read_all(&mut plan, &mut handler.source);
+        assert_eq!(body, handler.source.get_ref()[900..].to_vec());
+    }
+
+    #[test]
+    fn failed_if_range_serves_everything_despite_skip() {
+        let mut handler = handler();
+        handler.etag = Some("\"v2\"".to_owned());
+        let mut plan = handler.plan_for(Some(&["bytes=100-".to_owned()]), Some(&"\"v1\"".to_owned()));
+        assert_eq!(plan.status, 200);
+        // CEF doesn't know about If-Range and still skips to the first byte.
+        assert_eq!(plan.skip(100), 100);
+        let body = read_all(&mut plan, &mut handler.source);
+        assert_eq!(body, handler.source.get_ref().to_vec());
+    }
+
+    #[test]
+    fn skip_without_range_advances() {
+        let mut handler = handler();
+        let mut plan = handler.plan_for(None, None);
+        assert_eq!(plan.status, 200);
+        assert_eq!(plan.skip(100), 100);
+        let body = read_all(&mut plan, &mut handler.source);
+        assert_eq!(body, handler.source.get_ref()[100..].to_vec());
+    }
+
+    #[test]
+    fn skip_after_reading_advances() {
+        let mut handler = handler();
+        let mut plan = handler.plan_for(Some(&["bytes=100-199".to_owned()]), None);
+        let mut buffer = [0; 10];
+        assert_eq!(plan.read(&mut handler.source, &mut buffer).unwrap(), 10);
+        assert_eq!(plan.skip(10), 10);
+        let body = read_all(&mut plan, &mut handler.source);
+        assert_eq!(body, handler.source.get_ref()[120..200].to_vec());
+    }
+
+    #[test]
+    fn multiple_ranges_are_multipart() {
+        let mut handler = handler();
+        let mut plan = handler.plan_for(Some(&["bytes=0-1,10-11".to_owned()]), None);
+        assert!(plan.mime_type.starts_with("multipart/byteranges; boundary="));
+        let body = String::from_utf8_lossy(&read_all(&mut plan, &mut handler.source)).into_owned();
+        assert!(body.contains("Content-Range: bytes 0-1/1000"));
+        assert!(body.contains("Content-Range: bytes 10-11/1000"));
+    }
+}
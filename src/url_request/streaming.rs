@@ -0,0 +1,334 @@
+//! Resource handlers that stream their response body from an asynchronous
+//! producer, such as `text/event-stream` endpoints on a custom scheme.
+//!
+//! Reads that find no data are parked and resumed once the producer yields
+//! more, so CEF only pulls as fast as the producer pushes. With
+//! [ResourceHandler::from_channel], a full channel makes the producer wait until
+//! the page has consumed some of the data.
+
+use super::{ResourceHandler, ResourceHandlerCallbacks, ResourceReadHandler, ResourceSkipCallback};
+use crate::{
+    callback::Callback,
+    load_handler::ErrorCode,
+    request::Request,
+    response::Response,
+    task::{TaskRunner, ThreadId},
+};
+use futures::{
+    channel::mpsc,
+    stream::Stream,
+    task::{waker_ref, ArcWake},
+};
+use parking_lot::Mutex;
+use std::{
+    fmt,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+/// The status line and headers of a streamed response.
+#[derive(Debug, Clone)]
+pub struct StreamResponse {
+    status: i32,
+    status_text: String,
+    mime_type: String,
+    charset: String,
+    headers: Vec<(String, String)>,
+    length: Option<u64>,
+}
+
+impl StreamResponse {
+    /// Creates a 200 response with the given MIME type and an unknown length.
+    pub fn new(mime_type: &str) -> Self {
+        StreamResponse {
+            status: 200,
+            status_text: "OK".to_owned(),
+            mime_type: mime_type.to_owned(),
+            charset: String::new(),
+            headers: Vec::new(),
+            length: None,
+        }
+    }
+    /// Creates a response for server-sent events: a `text/event-stream` in UTF-8
+    /// that isn't cached.
+    pub fn event_stream() -> Self {
+        StreamResponse::new("text/event-stream")
+            .charset("utf-8")
+            .header("Cache-Control", "no-cache")
+    }
+    /// Sets the HTTP status code and status text.
+    pub fn status(mut self, status: i32, status_text: &str) -> Self {
+        self.status = status;
+        self.status_text = status_text.to_owned();
+        self
+    }
+    /// Sets the charset of the response.
+    pub fn charset(mut self, charset: &str) -> Self {
+        self.charset = charset.to_owned();
+        self
+    }
+    /// Adds a header, replacing earlier headers with the same name.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+    /// Sets the length of the body. CEF stops reading once this many bytes were
+    /// streamed, and fails the request if the stream ends early.
+    pub fn length(mut self, length: u64) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    fn apply(&self, response: &Response) {
+        response.set_status(self.status);
+        response.set_status_text(&self.status_text);
+        response.set_mime_type(&self.mime_type);
+        if !self.charset.is_empty() {
+            response.set_charset(&self.charset);
+        }
+        for (name, value) in &self.headers {
+            response.set_header_by_name(name, value, true);
+        }
+    }
+}
+
+/// A single server-sent event, formatted for a `text/event-stream` body with
+/// [ToString::to_string] or [Vec::from].
+///
+/// ```ignore
+/// let (handler, mut sender) = ResourceHandler::from_channel(StreamResponse::event_stream(), 16);
+/// sender.try_send(ServerSentEvent::new("42%").event("progress").into())?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerSentEvent {
+    event: Option<String>,
+    data: String,
+    id: Option<String>,
+    retry: Option<u64>,
+}
+
+impl ServerSentEvent {
+    /// Creates a message event with `data`. Line breaks in `data` are preserved.
+    pub fn new(data: &str) -> Self {
+        ServerSentEvent {
+            data: data.to_owned(),
+            ..Default::default()
+        }
+    }
+    /// Sets the event type, which selects the `EventSource` listener.
+    pub fn event(mut self, event: &str) -> Self {
+        self.event = Some(event.to_owned());
+        self
+    }
+    /// Sets the event ID, which the browser sends back in a `Last-Event-ID`
+    /// header when it reconnects.
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_owned());
+        self
+    }
+    /// Sets the time in milliseconds the browser waits before reconnecting.
+    pub fn retry(mut self, milliseconds: u64) -> Self {
+        self.retry = Some(milliseconds);
+        self
+    }
+}
+
+impl fmt::Display for ServerSentEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Field values end at the first line break, so strip them from the
+        // single-line fields.
+        let single_line = |value: &str| value.replace(&['\r', '\n'][..], "");
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {}", single_line(event))?;
+        }
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {}", single_line(id))?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {}", retry)?;
+        }
+        for line in self.data.split('\n') {
+            writeln!(f, "data: {}", line.strip_suffix('\r').unwrap_or(line))?;
+        }
+        writeln!(f)
+    }
+}
+
+impl From<ServerSentEvent> for Vec<u8> {
+    fn from(event: ServerSentEvent) -> Vec<u8> {
+        event.to_string().into_bytes()
+    }
+}
+
+impl ResourceHandler {
+    /// Creates a handler that answers with `response` and streams the items of
+    /// `stream` as the body. The response is complete when the stream ends.
+    ///
+    /// `stream` is polled on the IO thread, and only when CEF wants more data.
+    /// It is dropped when the response is complete or the request is canceled.
+    pub fn from_stream<S, B>(response: StreamResponse, stream: S) -> ResourceHandler
+    where
+        S: Stream<Item = B> + Send + 'static,
+        B: AsRef<[u8]> + Send + 'static,
+    {
+        ResourceHandler::new(StreamResourceHandler {
+            response,
+            shared: Arc::new(Shared {
+                state: Mutex::new(StreamState {
+                    stream: Some(Box::pin(stream)),
+                    pending: None,
+                    parked: None,
+                }),
+                scheduled: AtomicBool::new(false),
+            }),
+        })
+    }
+    /// Creates a handler that answers with `response` and streams the chunks sent
+    /// through the returned sender as the body. The response is complete when all
+    /// senders are dropped.
+    ///
+    /// Up to `buffer` chunks per sender are queued before sending waits (or
+    /// [try_send](mpsc::Sender::try_send) fails) until the page has read some of
+    /// them. Once the request is canceled, sending fails with a disconnected
+    /// error.
+    pub fn from_channel(response: StreamResponse, buffer: usize) -> (ResourceHandler, mpsc::Sender<Vec<u8>>) {
+        let (sender, receiver) = mpsc::channel(buffer);
+        (ResourceHandler::from_stream(response, receiver), sender)
+    }
+}
+
+type BoxStream<B> = Pin<Box<dyn Stream<Item = B> + Send>>;
+
+struct StreamState<B> {
+    /// None once the stream ended or the request was canceled.
+    stream: Option<BoxStream<B>>,
+    /// An item that didn't fit into the last buffer, and how much of it was
+    /// already read.
+    pending: Option<(B, usize)>,
+    /// A read waiting for the stream to yield.
+    parked: Option<ResourceReadHandler>,
+}
+
+enum Fill {
+    Read(usize),
+    Pending,
+}
+
+impl<B: AsRef<[u8]>> StreamState<B> {
+    /// Copies as much data as is available without waiting into `buffer`.
+    fn fill(&mut self, buffer: &mut [u8], cx: &mut Context) -> Fill {
+        let mut written = 0;
+        while written < buffer.len() {
+            if let Some((item, offset)) = &mut self.pending {
+                let data = &item.as_ref()[*offset..];
+                let count = data.len().min(buffer.len() - written);
+                buffer[written..written + count].copy_from_slice(&data[..count]);
+                written += count;
+                *offset += count;
+                if count == data.len() {
+                    self.pending = None;
+                }
+                continue;
+            }
+            let stream = match &mut self.stream {
+                Some(stream) => stream,
+                None => break,
+            };
+            match stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => self.pending = Some((item, 0)),
+                Poll::Ready(None) => self.stream = None,
+                Poll::Pending if written == 0 => return Fill::Pending,
+                Poll::Pending => break,
+            }
+        }
+        Fill::Read(written)
+    }
+    /// Answers `handler` if data is available or the stream ended. Returns the
+    /// handler if it has to wait.
+    fn read(&mut self, mut handler: ResourceReadHandler, cx: &mut Context) -> Option<ResourceReadHandler> {
+        match self.fill(handler.as_buffer_ref(), cx) {
+            Fill::Read(written) => {
+                handler.set_bytes_read(written as i32);
+                Some(handler)
+            }
+            Fill::Pending => {
+                self.parked = Some(handler);
+                None
+            }
+        }
+    }
+}
+
+struct Shared<B> {
+    state: Mutex<StreamState<B>>,
+    /// Whether a task resuming the parked read is already posted.
+    scheduled: AtomicBool,
+}
+
+impl<B: AsRef<[u8]> + Send + 'static> Shared<B> {
+    /// Resumes the parked read, if any, once the stream is ready.
+    fn resume(self: &Arc<Self>) {
+        self.scheduled.store(false, Ordering::SeqCst);
+        let waker = waker_ref(self);
+        let mut cx = Context::from_waker(&waker);
+        let mut state = self.state.lock();
+        if let Some(handler) = state.parked.take() {
+            if let Some(handler) = state.read(handler, &mut cx) {
+                drop(state);
+                handler.cont();
+            }
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + Send + 'static> ArcWake for Shared<B> {
+    /// Producers may wake the stream from any thread and while holding their
+    /// own locks, so the read is resumed in a separate task on the IO thread.
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        if !arc_self.scheduled.swap(true, Ordering::SeqCst) {
+            let shared = arc_self.clone();
+            TaskRunner::post_task_on(ThreadId::IO, move || shared.resume());
+        }
+    }
+}
+
+struct StreamResourceHandler<B> {
+    response: StreamResponse,
+    shared: Arc<Shared<B>>,
+}
+
+impl<B: AsRef<[u8]> + Send + 'static> ResourceHandlerCallbacks for StreamResourceHandler<B> {
+    fn open(&mut self, request: Request, handle_request: &mut bool, callback: Callback) -> bool {
+        *handle_request = true;
+        true
+    }
+    fn get_response_headers(
+        &self,
+        response: Response,
+        response_length: &mut Option<u64>,
+        redirect_url: &mut String,
+    ) {
+        self.response.apply(&response);
+        *response_length = self.response.length;
+    }
+    /// Streams can't seek, so range requests aren't satisfiable.
+    fn skip(&mut self, bytes_to_skip: u64, bytes_skipped: &mut u64, callback: ResourceSkipCallback) -> Result<(), ErrorCode> {
+        Err(ErrorCode::RequestRangeNotSatisfiable)
+    }
+    fn read(&mut self, handler: ResourceReadHandler) -> Option<ResourceReadHandler> {
+        let waker = waker_ref(&self.shared);
+        let mut cx = Context::from_waker(&waker);
+        self.shared.state.lock().read(handler, &mut cx)
+    }
+    fn cancel(&mut self) {
+        let mut state = self.shared.state.lock();
+        state.stream = None;
+        state.pending = None;
+        state.parked = None;
+    }
+}
@@ -0,0 +1,538 @@
+//! A [ResponseFilterCallbacks] implementation that rewrites text responses.
+//!
+//! Response data arrives in chunks of arbitrary size, so matches can straddle
+//! chunk boundaries and multibyte UTF-8 sequences can be split. The filter
+//! can hold back the end of a chunk, up to the longest possible match, until
+//! the next one arrives. CEF only promises another call once the response is
+//! complete if the output buffer was filled, though, so the end of a chunk is
+//! only held back while the output buffer is full. Otherwise it's written out,
+//! and a match or character split at that boundary isn't rewritten. Nothing
+//! is ever dropped. Responses that turn out not to be valid UTF-8 are passed
+//! through unchanged from the first invalid byte on.
+
+use super::{ResponseFilter, ResponseFilterCallbacks, ResponseFilterStatus};
+use crate::response::Response;
+use parking_lot::Mutex;
+use std::{ops::Range, sync::Arc};
+
+enum Matcher {
+    Literal { text: String, ignore_case: bool },
+    #[cfg(feature = "regex")]
+    Regex { regex: regex::Regex, max_len: usize },
+}
+
+struct RewriteRule {
+    matcher: Matcher,
+    replacement: String,
+    /// Whether the replacement is inserted before the match instead of
+    /// replacing it.
+    insert_before: bool,
+    /// Whether only the first match is rewritten.
+    once: bool,
+    html_only: bool,
+}
+
+impl RewriteRule {
+    /// The length of the longest text this rule can match.
+    fn max_len(&self) -> usize {
+        match &self.matcher {
+            Matcher::Literal { text, .. } => text.len(),
+            #[cfg(feature = "regex")]
+            Matcher::Regex { max_len, .. } => *max_len,
+        }
+    }
+
+    /// Finds the first non-empty match in `text` and returns its range and the
+    /// text to replace it with.
+    fn find(&self, text: &str) -> Option<(Range<usize>, String)> {
+        let (range, replacement) = match &self.matcher {
+            Matcher::Literal { text: pattern, ignore_case: false } => {
+                let start = text.find(&pattern[..])?;
+                (start..start + pattern.len(), self.replacement.clone())
+            }
+            Matcher::Literal { text: pattern, ignore_case: true } => {
+                let start = text
+                    .as_bytes()
+                    .windows(pattern.len())
+                    .position(|window| window.eq_ignore_ascii_case(pattern.as_bytes()))?;
+                (start..start + pattern.len(), self.replacement.clone())
+            }
+            #[cfg(feature = "regex")]
+            Matcher::Regex { regex, .. } => {
+                let captures = regex.captures_iter(text).find(|captures| !captures[0].is_empty())?;
+                let mut replacement = String::new();
+                captures.expand(&self.replacement, &mut replacement);
+                let found = captures.get(0).unwrap();
+                (found.start()..found.end(), replacement)
+            }
+        };
+        if self.insert_before {
+            Some((range.clone(), replacement + &text[range]))
+        } else {
+            Some((range, replacement))
+        }
+    }
+}
+
+/// Rewrites the text of responses with literal and regular expression
+/// replacements, and injects HTML into `text/html` documents.
+///
+/// Create the filter once and hand out a [ResponseFilter] per response with
+/// [TextRewriteFilter::filter_for]:
+///
+/// ```ignore
+/// let rewrite = TextRewriteFilter::new()
+///     .mime_type("text/html")
+///     .replace("https://api.example.com/", "https://staging-api.example.com/")
+///     .inject_before_head_end("<script src=\"app://assets/inject.js\"></script>");
+/// // In `ResourceRequestHandlerCallbacks::get_resource_response_filter`:
+/// rewrite.filter_for(&response)
+/// ```
+///
+/// Rules are applied in a single pass: at every position, the earliest match
+/// of any rule is rewritten, with ties going to the rule added first.
+/// Replaced text isn't matched again.
+#[derive(Clone, Default)]
+pub struct TextRewriteFilter {
+    mime_types: Vec<String>,
+    rules: Vec<Arc<RewriteRule>>,
+}
+
+impl TextRewriteFilter {
+    /// Creates a filter without rules that applies to every response.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Restricts the filter to responses with the given MIME type. Calling this
+    /// more than once allows any of the types.
+    pub fn mime_type(mut self, mime_type: &str) -> Self {
+        self.mime_types.push(mime_type.to_ascii_lowercase());
+        self
+    }
+    /// Replaces every occurrence of `find` with `replacement`. Empty patterns
+    /// are ignored.
+    pub fn replace(self, find: &str, replacement: &str) -> Self {
+        if find.is_empty() {
+            return self;
+        }
+        self.rule(RewriteRule {
+            matcher: Matcher::Literal {
+                text: find.to_owned(),
+                ignore_case: false,
+            },
+            replacement: replacement.to_owned(),
+            insert_before: false,
+            once: false,
+            html_only: false,
+        })
+    }
+    /// Replaces every non-empty match of `regex` with `replacement`, which may
+    /// refer to capture groups as in [regex::Captures::expand].
+    ///
+    /// Matches can only be found across chunk boundaries, where possible at all,
+    /// if they are at most `max_match_len` bytes long, so choose a pattern with
+    /// a bounded length.
+    /// Anchors like `^` match at the start of each chunk rather than the start of
+    /// the response.
+    #[cfg(feature = "regex")]
+    pub fn replace_regex(self, regex: regex::Regex, replacement: &str, max_match_len: usize) -> Self {
+        self.rule(RewriteRule {
+            matcher: Matcher::Regex {
+                regex,
+                max_len: max_match_len,
+            },
+            replacement: replacement.to_owned(),
+            insert_before: false,
+            once: false,
+            html_only: false,
+        })
+    }
+    /// Inserts `html` before the first `</head>` tag of `text/html` responses.
+    /// Documents without a `</head>` tag are left alone.
+    pub fn inject_before_head_end(self, html: &str) -> Self {
+        self.rule(RewriteRule {
+            matcher: Matcher::Literal {
+                text: "</head>".to_owned(),
+                ignore_case: true,
+            },
+            replacement: html.to_owned(),
+            insert_before: true,
+            once: true,
+            html_only: true,
+        })
+    }
+
+    fn rule(mut self, rule: RewriteRule) -> Self {
+        self.rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Returns a filter for `response`, or None if the filter doesn't apply to
+    /// its MIME type, the response has a charset other than UTF-8, or no rule
+    /// applies.
+    pub fn filter_for(&self, response: &Response) -> Option<ResponseFilter> {
+        let mime_type = response.get_mime_type().to_ascii_lowercase();
+        if !self.mime_types.is_empty() && !self.mime_types.contains(&mime_type) {
+            return None;
+        }
+        let charset = response.get_charset();
+        if !charset.is_empty() && !charset.eq_ignore_ascii_case("utf-8") && !charset.eq_ignore_ascii_case("utf8") {
+            return None;
+        }
+        let is_html = mime_type == "text/html";
+        let rules: Vec<_> = self.rules.iter().filter(|rule| is_html || !rule.html_only).cloned().collect();
+        if rules.is_empty() {
+            return None;
+        }
+        Some(ResponseFilter::new(TextRewriter(Mutex::new(RewriteState::new(rules)))))
+    }
+}
+
+struct RewriteState {
+    rules: Vec<Arc<RewriteRule>>,
+    /// Whether each rule still applies.
+    enabled: Vec<bool>,
+    /// Decoded text that wasn't rewritten yet.
+    text: String,
+    /// The start of a UTF-8 sequence split at the end of the last chunk.
+    incomplete: Vec<u8>,
+    /// The number of bytes still missing from a split UTF-8 sequence whose
+    /// start was already written out. They're copied as is.
+    continuation: usize,
+    /// Set after invalid UTF-8 was found; all further input is copied as is.
+    passthrough: bool,
+    /// Rewritten data that wasn't written yet, and how much of it was.
+    output: Vec<u8>,
+    output_position: usize,
+}
+
+impl RewriteState {
+    fn new(rules: Vec<Arc<RewriteRule>>) -> RewriteState {
+        RewriteState {
+            enabled: vec![true; rules.len()],
+            rules,
+            text: String::new(),
+            incomplete: Vec::new(),
+            continuation: 0,
+            passthrough: false,
+            output: Vec::new(),
+            output_position: 0,
+        }
+    }
+
+    fn push(&mut self, data: &[u8]) {
+        if self.passthrough {
+            self.output.extend_from_slice(data);
+            return;
+        }
+        let continued = data.iter().take(self.continuation).take_while(|byte| *byte & 0xc0 == 0x80).count();
+        self.output.extend_from_slice(&data[..continued]);
+        self.continuation = if continued == data.len() { self.continuation - continued } else { 0 };
+        let data = &data[continued..];
+        let mut bytes = std::mem::take(&mut self.incomplete);
+        bytes.extend_from_slice(data);
+        match std::str::from_utf8(&bytes) {
+            Ok(text) => self.text.push_str(text),
+            Err(error) => {
+                let (valid, rest) = bytes.split_at(error.valid_up_to());
+                self.text.push_str(std::str::from_utf8(valid).unwrap());
+                if error.error_len().is_none() {
+                    self.incomplete = rest.to_vec();
+                } else {
+                    self.rewrite(true);
+                    self.output.extend_from_slice(rest);
+                    self.passthrough = true;
+                }
+            }
+        }
+    }
+
+    /// Rewrites the pending text. Unless `complete` is set, text that may be
+    /// the start of a match is kept for the next chunk.
+    fn rewrite(&mut self, complete: bool) {
+        let safe = if complete {
+            self.text.len()
+        } else {
+            let hold_back = self
+                .rules
+                .iter()
+                .zip(&self.enabled)
+                .filter(|(_, enabled)| **enabled)
+                .map(|(rule, _)| rule.max_len().saturating_sub(1))
+                .max()
+                .unwrap_or(0);
+            let mut safe = self.text.len().saturating_sub(hold_back);
+            while !self.text.is_char_boundary(safe) {
+                safe -= 1;
+            }
+            safe
+        };
+        let mut position = 0;
+        loop {
+            let text = &self.text[position..];
+            let found = self
+                .rules
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| self.enabled[*index])
+                .filter_map(|(index, rule)| rule.find(text).map(|(range, replacement)| (index, range, replacement)))
+                .min_by_key(|(index, range, _)| (range.start, *index));
+            match found {
+                Some((index, range, replacement)) if position + range.end <= safe => {
+                    self.output.extend_from_slice(&text.as_bytes()[..range.start]);
+                    self.output.extend_from_slice(replacement.as_bytes());
+                    position += range.end;
+                    if self.rules[index].once {
+                        self.enabled[index] = false;
+                    }
+                }
+                Some((_, range, _)) => {
+                    let end = (position + range.start).min(safe);
+                    self.output.extend_from_slice(&self.text.as_bytes()[position..end]);
+                    position = end;
+                    break;
+                }
+                None => {
+                    self.output.extend_from_slice(&self.text.as_bytes()[position..safe]);
+                    position = safe;
+                    break;
+                }
+            }
+        }
+        self.text.drain(..position);
+    }
+
+    /// Rewrites and outputs all pending text as if the response ended here. A
+    /// split UTF-8 sequence is output as is, and the rest of it is copied once
+    /// it arrives.
+    fn flush(&mut self) {
+        self.rewrite(true);
+        let incomplete = std::mem::take(&mut self.incomplete);
+        if let Some(first) = incomplete.first() {
+            let len = match first {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                _ => 4,
+            };
+            self.continuation = len - incomplete.len();
+        }
+        self.output.extend_from_slice(&incomplete);
+    }
+
+    fn write(&mut self, data_out: &mut [u8]) -> usize {
+        let pending = &self.output[self.output_position..];
+        let count = pending.len().min(data_out.len());
+        data_out[..count].copy_from_slice(&pending[..count]);
+        self.output_position += count;
+        if self.output_position == self.output.len() {
+            self.output.clear();
+            self.output_position = 0;
+        }
+        count
+    }
+
+    fn has_output(&self) -> bool {
+        !self.output.is_empty()
+    }
+
+    fn has_pending(&self) -> bool {
+        self.has_output() || !self.text.is_empty() || !self.incomplete.is_empty()
+    }
+}
+
+struct TextRewriter(Mutex<RewriteState>);
+
+impl ResponseFilterCallbacks for TextRewriter {
+    fn init_filter(&self) -> bool {
+        true
+    }
+    /// Text is only held back for the next chunk if `data_out` is filled, as
+    /// CEF only promises to call again with an empty `data_in` at the end of
+    /// the response in that case. An empty `data_in` flushes all pending text.
+    fn filter(
+        &self,
+        data_in: &[u8],
+        data_in_read: &mut usize,
+        data_out: &mut [u8],
+        data_out_written: &mut usize,
+    ) -> ResponseFilterStatus {
+        let mut state = self.0.lock();
+        let mut written = state.write(data_out);
+        if state.has_output() && written > 0 {
+            // The output is full; leave the input until there's room again.
+            *data_in_read = 0;
+            *data_out_written = written;
+            return ResponseFilterStatus::NeedMoreData;
+        }
+        *data_in_read = data_in.len();
+        if data_in.is_empty() {
+            state.flush();
+        } else {
+            state.push(data_in);
+            state.rewrite(false);
+        }
+        written += state.write(&mut data_out[written..]);
+        if written < data_out.len() {
+            // This may have been the last chunk, and CEF won't call again.
+            state.flush();
+            written += state.write(&mut data_out[written..]);
+        }
+        *data_out_written = written;
+        if state.has_pending() {
+            ResponseFilterStatus::NeedMoreData
+        } else {
+            ResponseFilterStatus::Done
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewriter(filter: &TextRewriteFilter) -> TextRewriter {
+        TextRewriter(Mutex::new(RewriteState::new(filter.rules.clone())))
+    }
+
+    /// Feeds `input` to the filter following the call sequence documented for
+    /// [ResponseFilterCallbacks::filter]: in chunks of `chunk_len` bytes with an
+    /// output buffer of `out_len` bytes, calling again until each chunk was
+    /// read, and with an empty input after the last chunk only while the filter
+    /// fills the output buffer and returns [ResponseFilterStatus::NeedMoreData].
+    fn run(filter: &TextRewriteFilter, input: &[u8], chunk_len: usize, out_len: usize) -> Vec<u8> {
+        let rewriter = rewriter(filter);
+        let mut output = Vec::new();
+        let mut data_out = vec![0; out_len];
+        let mut filled = false;
+        for mut chunk in input.chunks(chunk_len) {
+            while !chunk.is_empty() {
+                let (mut read, mut written) = (0, 0);
+                let status = rewriter.filter(chunk, &mut read, &mut data_out, &mut written);
+                assert!(read > 0 || written > 0);
+                output.extend_from_slice(&data_out[..written]);
+                filled = status == ResponseFilterStatus::NeedMoreData && written == out_len;
+                chunk = &chunk[read..];
+            }
+        }
+        while filled {
+            let (mut read, mut written) = (0, 0);
+            let status = rewriter.filter(&[], &mut read, &mut data_out, &mut written);
+            output.extend_from_slice(&data_out[..written]);
+            filled = status == ResponseFilterStatus::NeedMoreData && written == out_len;
+        }
+        output
+    }
+
+    #[test]
+    fn rewrites_matches_split_across_chunks_while_output_is_full() {
+        let filter = TextRewriteFilter::new().replace("world", "there").replace("!", "?");
+        let input = b"hello world, worl world!";
+        let expected = b"hello there, worl there?".to_vec();
+        assert_eq!(run(&filter, input, 8, 1), expected);
+        assert_eq!(run(&filter, input, 10, 3), expected);
+        assert_eq!(run(&filter, input, input.len(), 64), expected);
+    }
+
+    #[test]
+    fn never_drops_text_held_back_for_the_next_chunk() {
+        let filter = TextRewriteFilter::new().replace("world", "there").replace("!", "?");
+        let input = b"hello world, worl world!";
+        // The output buffer isn't filled, so the text held back for a match is
+        // written out after every chunk instead.
+        assert_eq!(run(&filter, input, 1, 64), b"hello world, worl world?".to_vec());
+        assert_eq!(run(&filter, input, 9, 64), b"hello world, worl there?".to_vec());
+        for chunk_len in 1..=input.len() {
+            for out_len in 1..=8 {
+                let output = run(&filter, input, chunk_len, out_len);
+                assert!(output.ends_with(b"?"), "chunk_len {}, out_len {}: {:?}", chunk_len, out_len, output);
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_characters_split_across_chunks() {
+        let filter = TextRewriteFilter::new().replace("ü", "ue").replace("€", "EUR").replace("5", "five");
+        let input = "Grüße aus München für 5 €, 日本".as_bytes();
+        let expected = "Grueße aus Muenchen fuer five EUR, 日本".as_bytes().to_vec();
+        assert_eq!(run(&filter, input, input.len(), 64), expected);
+        // Characters split while the output buffer has room are copied as is,
+        // and don't stop the rest of the response from being rewritten.
+        let expected = "Grüße aus München für five €, 日本".as_bytes().to_vec();
+        assert_eq!(run(&filter, input, 1, 64), expected);
+        let filter = TextRewriteFilter::new().replace("x", "y");
+        for chunk_len in 1..=5 {
+            for out_len in 1..=5 {
+                assert_eq!(run(&filter, input, chunk_len, out_len), input.to_vec());
+            }
+        }
+    }
+
+    #[test]
+    fn passes_invalid_utf8_through() {
+        let filter = TextRewriteFilter::new().replace("world", "there");
+        let input = b"world caf\xe9 world";
+        assert_eq!(run(&filter, input, input.len(), 64), b"there caf\xe9 world".to_vec());
+        assert_eq!(run(&filter, input, 8, 1), b"there caf\xe9 world".to_vec());
+    }
+
+    #[test]
+    fn waits_for_room_in_output() {
+        let rewriter = rewriter(&TextRewriteFilter::new().replace("x", "y"));
+        let mut data_out = [0; 2];
+        let (mut read, mut written) = (0, 0);
+        let status = rewriter.filter(b"abcdef", &mut read, &mut data_out, &mut written);
+        assert_eq!((status, read, written), (ResponseFilterStatus::NeedMoreData, 6, 2));
+        assert_eq!(&data_out, b"ab");
+
+        // The output is still full, so the next chunk isn't read.
+        let status = rewriter.filter(b"gh", &mut read, &mut data_out, &mut written);
+        assert_eq!((status, read, written), (ResponseFilterStatus::NeedMoreData, 0, 2));
+        assert_eq!(&data_out, b"cd");
+
+        // Once the pending output fits, the next chunk is read as well.
+        let status = rewriter.filter(b"gh", &mut read, &mut data_out, &mut written);
+        assert_eq!((status, read, written), (ResponseFilterStatus::NeedMoreData, 2, 2));
+        assert_eq!(&data_out, b"ef");
+
+        let status = rewriter.filter(&[], &mut read, &mut data_out, &mut written);
+        assert_eq!((status, read, written), (ResponseFilterStatus::Done, 0, 2));
+        assert_eq!(&data_out, b"gh");
+    }
+
+    #[test]
+    fn writes_held_back_text_without_flush_call() {
+        let filter = TextRewriteFilter::new()
+            .replace("abc", "x")
+            .inject_before_head_end("<script></script>");
+        let rewriter = rewriter(&filter);
+        let mut data_out = [0; 64];
+        let (mut read, mut written) = (0, 0);
+        // CEF doesn't call again with an empty input, as the output isn't full.
+        let status = rewriter.filter(b"<p>zab", &mut read, &mut data_out, &mut written);
+        assert_eq!((status, read), (ResponseFilterStatus::Done, 6));
+        assert_eq!(&data_out[..written], b"<p>zab");
+    }
+
+    #[test]
+    fn holds_back_text_while_output_is_full() {
+        let rewriter = rewriter(&TextRewriteFilter::new().replace("abc", "x"));
+        let mut data_out = [0; 2];
+        let (mut read, mut written) = (0, 0);
+        let status = rewriter.filter(b"12ab", &mut read, &mut data_out, &mut written);
+        assert_eq!((status, read, written), (ResponseFilterStatus::NeedMoreData, 4, 2));
+        assert_eq!(&data_out, b"12");
+
+        let status = rewriter.filter(b"c3", &mut read, &mut data_out, &mut written);
+        assert_eq!((status, read, written), (ResponseFilterStatus::Done, 2, 2));
+        assert_eq!(&data_out, b"x3");
+    }
+
+    #[test]
+    fn injects_before_first_head_end() {
+        let filter = TextRewriteFilter::new().inject_before_head_end("<script></script>");
+        let input = b"<html><head><title>t</title></HEAD><body></head></body>";
+        let expected = b"<html><head><title>t</title><script></script></HEAD><body></head></body>".to_vec();
+        assert_eq!(run(&filter, input, input.len(), 64), expected);
+        assert_eq!(run(&filter, input, 10, 1), expected);
+    }
+}
@@ -30,6 +30,16 @@ use crate::{
 };
 use bitflags::bitflags;
 
+mod class;
+mod convert;
+mod promise;
+pub use self::class::{V8Class, V8ClassBuilder};
+pub(crate) use self::class::release_instances;
+pub use self::convert::{ArrayBuffer, FromV8, IntoV8, IntoV8Function};
+pub(crate) use self::convert::release_helpers;
+pub use self::promise::PromiseResolver;
+pub(crate) use self::promise::reject_pending_promises;
+
 ref_counted_ptr! {
     /// Structure representing a V8 context handle. V8 handles can only be accessed
     /// from the thread on which they are created. Valid threads for creating a V8
@@ -519,12 +529,18 @@ impl V8Value {
     pub fn new_function(
         name: &str,
         handler: impl Fn(&str, V8Value, &[V8Value]) -> Result<V8Value, String> + Send + 'static,
+    ) -> Self {
+        Self::new_function_boxed(name, Box::new(handler))
+    }
+    pub(crate) fn new_function_boxed(
+        name: &str,
+        handler: Box<dyn Fn(&str, V8Value, &[V8Value]) -> Result<V8Value, String> + Send + 'static>,
     ) -> Self {
         let name = CefString::new(name);
         unsafe {
             V8Value::from_ptr_unchecked(cef_v8value_create_function(
                 name.as_ptr(),
-                V8HandlerWrapper::new(Box::new(handler)).wrap().into_raw(),
+                V8HandlerWrapper::new(handler).wrap().into_raw(),
             ))
         }
     }
@@ -670,16 +686,23 @@ impl V8Value {
             None
         }
     }
-    /// Return a Date value.
+    /// Return a Date value. Dates before 1970 are supported; invalid dates
+    /// (`new Date(NaN)`) and dates the platform can't represent return None.
     pub fn get_date_value(&self) -> Option<SystemTime> {
         if self.is_date() {
-            self.0.get_date_value.map(|get_date_value| {
+            self.0.get_date_value.and_then(|get_date_value| {
                 let value = unsafe { get_date_value(self.as_ptr()) };
                 let mut fvalue = 0.0;
                 unsafe {
                     cef_time_to_doublet(&value, &mut fvalue);
                 }
-                SystemTime::UNIX_EPOCH + Duration::from_secs_f64(fvalue)
+                if !fvalue.is_finite() {
+                    None
+                } else if fvalue < 0.0 {
+                    SystemTime::UNIX_EPOCH.checked_sub(Duration::from_secs_f64(-fvalue))
+                } else {
+                    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs_f64(fvalue))
+                }
             })
         } else {
             None
@@ -0,0 +1,284 @@
+//! Binding Rust types to JavaScript objects.
+//!
+//! A [V8Class] describes the methods and properties of a Rust type `T` once.
+//! Instances are JavaScript objects whose [UserData](super::UserData) holds the
+//! Rust value; method and property handlers resolve `this` back to it. The Rust
+//! value is dropped when V8 collects the object or, at the latest, when the
+//! context that created it is released. Using an object after its context was
+//! released throws an `Error`.
+//!
+//! ```ignore
+//! let counter = V8Class::<Counter>::builder("Counter")
+//!     .constructor(|args| Ok(Counter { count: 0 }))
+//!     .method("increment", |counter, _args| {
+//!         counter.count += 1;
+//!         Ok(V8Value::from(counter.count))
+//!     })
+//!     .getter("count", |counter| Ok(V8Value::from(counter.count)))
+//!     .build();
+//! // In `on_context_created`:
+//! context.get_global().unwrap().set_value_bykey("Counter", counter.constructor(), V8PropertyAttribute::empty());
+//! ```
+
+use super::{
+    V8Accessor, V8AccessorCallbacks, V8AccessControl, V8Context, V8PropertyAttribute, V8Value,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+
+type Constructor<T> = Box<dyn Fn(&[V8Value]) -> Result<T, String> + Send + Sync>;
+type Method<T> = Box<dyn Fn(&mut T, &[V8Value]) -> Result<V8Value, String> + Send + Sync>;
+type Getter<T> = Box<dyn Fn(&T) -> Result<V8Value, String> + Send + Sync>;
+type Setter<T> = Box<dyn Fn(&mut T, &V8Value) -> Result<(), String> + Send + Sync>;
+
+struct Property<T> {
+    getter: Option<Getter<T>>,
+    setter: Option<Setter<T>>,
+}
+
+struct ClassDefinition<T> {
+    name: String,
+    constructor: Option<Constructor<T>>,
+    methods: Vec<(String, Arc<Method<T>>)>,
+    properties: HashMap<String, Property<T>>,
+}
+
+/// Builds a [V8Class].
+pub struct V8ClassBuilder<T> {
+    definition: ClassDefinition<T>,
+}
+
+impl<T: Send + 'static> V8ClassBuilder<T> {
+    /// Sets the function that creates the Rust value when the constructor
+    /// returned by [V8Class::constructor] is called from JavaScript. An `Err` is
+    /// thrown as an exception.
+    pub fn constructor(
+        mut self,
+        constructor: impl Fn(&[V8Value]) -> Result<T, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.definition.constructor = Some(Box::new(constructor));
+        self
+    }
+    /// Adds a method. An `Err` is thrown as an exception.
+    pub fn method(
+        mut self,
+        name: &str,
+        method: impl Fn(&mut T, &[V8Value]) -> Result<V8Value, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.definition.methods.push((name.to_owned(), Arc::new(Box::new(method))));
+        self
+    }
+    /// Adds a getter for the property `name`. Properties without a setter are
+    /// read-only.
+    pub fn getter(
+        mut self,
+        name: &str,
+        getter: impl Fn(&T) -> Result<V8Value, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.property(name).getter = Some(Box::new(getter));
+        self
+    }
+    /// Adds a setter for the property `name`. Properties without a getter read as
+    /// `undefined`.
+    pub fn setter(
+        mut self,
+        name: &str,
+        setter: impl Fn(&mut T, &V8Value) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.property(name).setter = Some(Box::new(setter));
+        self
+    }
+    pub fn build(self) -> V8Class<T> {
+        V8Class(Arc::new(self.definition))
+    }
+
+    fn property(&mut self, name: &str) -> &mut Property<T> {
+        self.definition.properties
+            .entry(name.to_owned())
+            .or_insert(Property {
+                getter: None,
+                setter: None,
+            })
+    }
+}
+
+/// A Rust type bound to JavaScript. Cloning a class is cheap; all clones share
+/// the same definition.
+pub struct V8Class<T>(Arc<ClassDefinition<T>>);
+
+impl<T> Clone for V8Class<T> {
+    fn clone(&self) -> Self {
+        V8Class(self.0.clone())
+    }
+}
+
+/// The shared state of an instance. `value` becomes None once the context that
+/// created the instance is released.
+struct InstanceState<T> {
+    value: Mutex<Option<T>>,
+}
+
+/// Stored in the [UserData](super::UserData) of instances.
+struct Instance<T>(Arc<InstanceState<T>>);
+
+trait Release: Send + Sync {
+    fn release(&self);
+}
+
+impl<T: Send> Release for InstanceState<T> {
+    fn release(&self) {
+        let value = self.value.lock().take();
+        drop(value);
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref INSTANCES: Mutex<Vec<(V8Context, Weak<dyn Release>)>> = Mutex::new(Vec::new());
+}
+
+/// Drops the Rust values of all instances created in `context`. Called when the
+/// context is released.
+pub(crate) fn release_instances(context: &V8Context) {
+    let released = {
+        let mut instances = INSTANCES.lock();
+        let mut released = Vec::new();
+        instances.retain(|(instance_context, instance)| {
+            if instance.strong_count() == 0 {
+                false
+            } else if instance_context.is_same(context) {
+                released.push(instance.clone());
+                false
+            } else {
+                true
+            }
+        });
+        released
+    };
+    for instance in released.iter().filter_map(Weak::upgrade) {
+        instance.release();
+    }
+}
+
+impl<T: Send + 'static> V8Class<T> {
+    pub fn builder(name: &str) -> V8ClassBuilder<T> {
+        V8ClassBuilder {
+            definition: ClassDefinition {
+                name: name.to_owned(),
+                constructor: None,
+                methods: Vec::new(),
+                properties: HashMap::new(),
+            },
+        }
+    }
+
+    /// Returns the class name.
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Creates a JavaScript object backed by `value`. Must be called with a
+    /// context entered.
+    pub fn new_instance(&self, value: T) -> V8Value {
+        let state = Arc::new(InstanceState {
+            value: Mutex::new(Some(value)),
+        });
+        if let Some(context) = V8Context::get_current() {
+            let release: Arc<dyn Release> = state.clone();
+            let mut instances = INSTANCES.lock();
+            instances.retain(|(_, instance)| instance.strong_count() > 0);
+            instances.push((context, Arc::downgrade(&release)));
+        }
+
+        let accessor = if self.0.properties.is_empty() {
+            None
+        } else {
+            Some(V8Accessor::new(ClassAccessor(self.clone())))
+        };
+        let object = V8Value::new_object(accessor, None);
+        object.set_user_data(Instance(state));
+        for name in self.0.properties.keys() {
+            object.set_value_byaccessor(name, V8AccessControl::empty(), V8PropertyAttribute::DONT_DELETE);
+        }
+        for (name, method) in &self.0.methods {
+            let class = self.clone();
+            let method = method.clone();
+            let function = V8Value::new_function(name, move |name, this, args| {
+                class.with_this(name, &this, |value| method(value, args))
+            });
+            object.set_value_bykey(name, function, V8PropertyAttribute::DONT_ENUM);
+        }
+        object
+    }
+
+    /// Returns a JavaScript function that creates instances with the constructor
+    /// set on the builder, whether or not it is called with `new`. Must be called
+    /// with a context entered.
+    ///
+    /// The created objects are plain objects, so `instanceof` does not work on
+    /// them.
+    pub fn constructor(&self) -> V8Value {
+        let class = self.clone();
+        V8Value::new_function(&self.0.name, move |_, _, args| {
+            let constructor = match &class.0.constructor {
+                Some(constructor) => constructor,
+                None => return Err(format!("{} is not a constructor", class.0.name)),
+            };
+            constructor(args).map(|value| class.new_instance(value))
+        })
+    }
+
+    /// Calls `f` with the Rust value backing `object`. Returns None if `object`
+    /// isn't an instance of this class, its context was released, or the value
+    /// is currently borrowed by a method further up the stack.
+    pub fn with_instance<R>(&self, object: &V8Value, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let user_data = object.get_user_data()?;
+        let instance = user_data.downcast_ref::<Instance<T>>()?;
+        let mut value = instance.0.value.try_lock()?;
+        value.as_mut().map(f)
+    }
+
+    /// Resolves `this` for a handler and reports problems as JavaScript
+    /// exceptions.
+    fn with_this(
+        &self,
+        member: &str,
+        this: &V8Value,
+        f: impl FnOnce(&mut T) -> Result<V8Value, String>,
+    ) -> Result<V8Value, String> {
+        let user_data = this.get_user_data();
+        let instance = match user_data.as_ref().and_then(|user_data| user_data.downcast_ref::<Instance<T>>()) {
+            Some(instance) => instance,
+            None => return Err(format!("{}.{} called on an incompatible object", self.0.name, member)),
+        };
+        let mut value = instance.0.value.try_lock()
+            .ok_or_else(|| format!("{} is already in use", self.0.name))?;
+        match value.as_mut() {
+            Some(value) => f(value),
+            None => Err(format!("{} belongs to a released context", self.0.name)),
+        }
+    }
+}
+
+struct ClassAccessor<T>(V8Class<T>);
+
+impl<T: Send + 'static> V8AccessorCallbacks for ClassAccessor<T> {
+    fn get(&self, name: &str, object: &V8Value) -> Result<V8Value, String> {
+        let class = &self.0;
+        match class.0.properties.get(name).and_then(|property| property.getter.as_ref()) {
+            Some(getter) => class.with_this(name, object, |value| getter(value)),
+            None => Ok(V8Value::undefined()),
+        }
+    }
+    fn set(&self, name: &str, object: &V8Value, new_value: &V8Value) -> Result<(), String> {
+        let class = &self.0;
+        match class.0.properties.get(name).and_then(|property| property.setter.as_ref()) {
+            Some(setter) => class
+                .with_this(name, object, |value| setter(value, new_value).map(|()| V8Value::undefined()))
+                .map(|_| ()),
+            None => Err(format!("{}.{} is read-only", class.0.name, name)),
+        }
+    }
+}
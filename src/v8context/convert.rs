@@ -0,0 +1,389 @@
+//! Conversions between Rust types and [V8Value], and native JavaScript functions
+//! built from plain Rust closures.
+
+use super::{V8Context, V8PropertyAttribute, V8Value};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    convert::TryFrom,
+    fmt::Display,
+    hash::BuildHasher,
+    time::SystemTime,
+};
+
+/// Types that can be created from a [V8Value]. The conversions are strict: a
+/// string is not converted to a number and a number is not converted to a
+/// string. The error describes the expected type.
+///
+/// Like all V8 functions, this may only be called with a context entered.
+pub trait FromV8: Sized {
+    fn from_v8(value: &V8Value) -> Result<Self, String>;
+}
+
+/// Types that can be converted into a [V8Value].
+///
+/// Like all V8 functions, this may only be called with a context entered.
+pub trait IntoV8 {
+    fn into_v8(self) -> Result<V8Value, String>;
+}
+
+/// The contents of a JavaScript `ArrayBuffer`. Converting from V8 also accepts
+/// typed arrays and `DataView`s, and copies the bytes they cover.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ArrayBuffer(pub Vec<u8>);
+
+impl From<Vec<u8>> for ArrayBuffer {
+    fn from(bytes: Vec<u8>) -> Self {
+        ArrayBuffer(bytes)
+    }
+}
+
+impl From<ArrayBuffer> for Vec<u8> {
+    fn from(buffer: ArrayBuffer) -> Self {
+        buffer.0
+    }
+}
+
+fn describe(value: &V8Value) -> &'static str {
+    if value.is_undefined() {
+        "undefined"
+    } else if value.is_null() {
+        "null"
+    } else if value.is_bool() {
+        "a boolean"
+    } else if value.is_int() || value.is_uint() || value.is_double() {
+        "a number"
+    } else if value.is_string() {
+        "a string"
+    } else if value.is_date() {
+        "a date"
+    } else if value.is_array() {
+        "an array"
+    } else if value.is_array_buffer() {
+        "an ArrayBuffer"
+    } else if value.is_function() {
+        "a function"
+    } else {
+        "an object"
+    }
+}
+
+fn mismatch(expected: &str, value: &V8Value) -> String {
+    format!("expected {}, got {}", expected, describe(value))
+}
+
+/// Copies the bytes of an ArrayBuffer or view into a string with one UTF-16
+/// unit per byte. `fromCharCode` is applied to slices to stay below the
+/// engine's argument limit.
+const BYTES_HELPER: &str = "(function(value) {
+    var bytes;
+    if (value instanceof ArrayBuffer) bytes = new Uint8Array(value);
+    else if (ArrayBuffer.isView(value)) bytes = new Uint8Array(value.buffer, value.byteOffset, value.byteLength);
+    else return null;
+    var chunks = [];
+    for (var i = 0; i < bytes.length; i += 8192) chunks.push(String.fromCharCode.apply(null, bytes.subarray(i, i + 8192)));
+    return chunks.join('');
+})";
+
+thread_local! {
+    /// The compiled [BYTES_HELPER] of every context it was used in on this
+    /// thread.
+    static BYTES_HELPERS: RefCell<Vec<(V8Context, V8Value)>> = RefCell::new(Vec::new());
+}
+
+/// Returns the [BYTES_HELPER] of the current context, compiling it on first use.
+fn bytes_helper() -> Option<V8Value> {
+    let context = V8Context::get_current()?;
+    BYTES_HELPERS.with(|helpers| {
+        let mut helpers = helpers.borrow_mut();
+        if let Some((_, helper)) = helpers.iter().find(|(cached, _)| cached.is_same(&context)) {
+            return Some(helper.clone());
+        }
+        let helper = context.eval(BYTES_HELPER, "", 0).ok()?;
+        helpers.push((context, helper.clone()));
+        Some(helper)
+    })
+}
+
+/// Drops the cached helper of `context`. Called when the context is released.
+pub(crate) fn release_helpers(context: &V8Context) {
+    BYTES_HELPERS.with(|helpers| helpers.borrow_mut().retain(|(cached, _)| !cached.is_same(context)));
+}
+
+impl FromV8 for V8Value {
+    fn from_v8(value: &V8Value) -> Result<Self, String> {
+        Ok(value.clone())
+    }
+}
+
+impl FromV8 for bool {
+    fn from_v8(value: &V8Value) -> Result<Self, String> {
+        value.get_bool_value().ok_or_else(|| mismatch("a boolean", value))
+    }
+}
+
+impl FromV8 for f64 {
+    fn from_v8(value: &V8Value) -> Result<Self, String> {
+        value.get_int_value().map(f64::from)
+            .or_else(|| value.get_uint_value().map(f64::from))
+            .or_else(|| value.get_double_value())
+            .ok_or_else(|| mismatch("a number", value))
+    }
+}
+
+impl FromV8 for f32 {
+    fn from_v8(value: &V8Value) -> Result<Self, String> {
+        f64::from_v8(value).map(|value| value as f32)
+    }
+}
+
+macro_rules! integer_from_v8 {
+    ($($ty:ty),*) => {$(
+        impl FromV8 for $ty {
+            fn from_v8(value: &V8Value) -> Result<Self, String> {
+                let number = f64::from_v8(value).map_err(|_| mismatch("an integer", value))?;
+                if number.fract() != 0.0 || number < <$ty>::MIN as f64 || number > <$ty>::MAX as f64 {
+                    return Err(format!("expected an integer between {} and {}, got {}", <$ty>::MIN, <$ty>::MAX, number));
+                }
+                Ok(number as $ty)
+            }
+        }
+    )*};
+}
+
+integer_from_v8!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+impl FromV8 for String {
+    fn from_v8(value: &V8Value) -> Result<Self, String> {
+        value.get_string_value().ok_or_else(|| mismatch("a string", value))
+    }
+}
+
+impl FromV8 for SystemTime {
+    fn from_v8(value: &V8Value) -> Result<Self, String> {
+        value.get_date_value().ok_or_else(|| mismatch("a date", value))
+    }
+}
+
+impl<T: FromV8> FromV8 for Option<T> {
+    fn from_v8(value: &V8Value) -> Result<Self, String> {
+        if value.is_undefined() || value.is_null() {
+            Ok(None)
+        } else {
+            T::from_v8(value).map(Some)
+        }
+    }
+}
+
+impl<T: FromV8> FromV8 for Vec<T> {
+    fn from_v8(value: &V8Value) -> Result<Self, String> {
+        if !value.is_array() {
+            return Err(mismatch("an array", value));
+        }
+        (0..value.get_array_length())
+            .map(|index| {
+                let item = value.get_value_byindex(index).unwrap_or_else(V8Value::undefined);
+                T::from_v8(&item).map_err(|error| format!("element {}: {}", index, error))
+            })
+            .collect()
+    }
+}
+
+impl<T: FromV8, S: BuildHasher + Default> FromV8 for HashMap<String, T, S> {
+    fn from_v8(value: &V8Value) -> Result<Self, String> {
+        if !value.is_object() || value.is_array() || value.is_function() {
+            return Err(mismatch("an object", value));
+        }
+        value.get_keys().unwrap_or_default()
+            .into_iter()
+            .map(|key| {
+                let item = value.get_value_bykey(&key).unwrap_or_else(V8Value::undefined);
+                let item = T::from_v8(&item).map_err(|error| format!("property '{}': {}", key, error))?;
+                Ok((key, item))
+            })
+            .collect()
+    }
+}
+
+impl FromV8 for ArrayBuffer {
+    /// This CEF version can't read the memory of an ArrayBuffer, so the bytes
+    /// are copied into a JavaScript string by a helper compiled once per
+    /// context and then decoded. Expect a few copies of the buffer per
+    /// conversion; large binary data is better passed some other way.
+    fn from_v8(value: &V8Value) -> Result<Self, String> {
+        let to_string = bytes_helper().ok_or_else(|| "no current context".to_owned())?;
+        let bytes = to_string.execute_function(None, std::slice::from_ref(value))
+            .and_then(|bytes| bytes.get_string_value())
+            .ok_or_else(|| mismatch("an ArrayBuffer", value))?;
+        bytes.chars()
+            .map(|byte| u8::try_from(u32::from(byte)))
+            .collect::<Result<_, _>>()
+            .map(ArrayBuffer)
+            .map_err(|_| "the ArrayBuffer was altered while it was copied".to_owned())
+    }
+}
+
+impl IntoV8 for V8Value {
+    fn into_v8(self) -> Result<V8Value, String> {
+        Ok(self)
+    }
+}
+
+impl IntoV8 for () {
+    fn into_v8(self) -> Result<V8Value, String> {
+        Ok(V8Value::undefined())
+    }
+}
+
+impl IntoV8 for bool {
+    fn into_v8(self) -> Result<V8Value, String> {
+        Ok(V8Value::from(self))
+    }
+}
+
+macro_rules! into_v8_via {
+    ($via:ty: $($ty:ty),*) => {$(
+        impl IntoV8 for $ty {
+            fn into_v8(self) -> Result<V8Value, String> {
+                Ok(V8Value::from(<$via>::from(self)))
+            }
+        }
+    )*};
+}
+
+into_v8_via!(i32: i8, i16, i32, u8, u16);
+into_v8_via!(u32: u32);
+into_v8_via!(f64: f32, f64);
+
+impl IntoV8 for i64 {
+    /// JavaScript numbers are doubles, so values beyond 2^53 lose precision.
+    fn into_v8(self) -> Result<V8Value, String> {
+        Ok(i32::try_from(self).map(V8Value::from).unwrap_or_else(|_| V8Value::from(self as f64)))
+    }
+}
+
+impl IntoV8 for u64 {
+    /// JavaScript numbers are doubles, so values beyond 2^53 lose precision.
+    fn into_v8(self) -> Result<V8Value, String> {
+        Ok(u32::try_from(self).map(V8Value::from).unwrap_or_else(|_| V8Value::from(self as f64)))
+    }
+}
+
+impl IntoV8 for &str {
+    fn into_v8(self) -> Result<V8Value, String> {
+        Ok(V8Value::from(self))
+    }
+}
+
+impl IntoV8 for String {
+    fn into_v8(self) -> Result<V8Value, String> {
+        Ok(V8Value::from(self.as_str()))
+    }
+}
+
+impl IntoV8 for SystemTime {
+    fn into_v8(self) -> Result<V8Value, String> {
+        V8Value::try_from(self).map_err(|error| format!("date out of range: {}", error))
+    }
+}
+
+impl<T: IntoV8> IntoV8 for Option<T> {
+    fn into_v8(self) -> Result<V8Value, String> {
+        match self {
+            Some(value) => value.into_v8(),
+            None => Ok(V8Value::null()),
+        }
+    }
+}
+
+impl<T: IntoV8> IntoV8 for Vec<T> {
+    fn into_v8(self) -> Result<V8Value, String> {
+        let array = V8Value::new_array(self.len() as i32);
+        for (index, item) in self.into_iter().enumerate() {
+            array.set_value_byindex(index as i32, item.into_v8()?);
+        }
+        Ok(array)
+    }
+}
+
+impl<T: IntoV8, S: BuildHasher> IntoV8 for HashMap<String, T, S> {
+    fn into_v8(self) -> Result<V8Value, String> {
+        let object = V8Value::new_object(None, None);
+        for (key, item) in self {
+            object.set_value_bykey(&key, item.into_v8()?, V8PropertyAttribute::empty());
+        }
+        Ok(object)
+    }
+}
+
+impl IntoV8 for ArrayBuffer {
+    fn into_v8(self) -> Result<V8Value, String> {
+        Ok(V8Value::new_array_buffer(self.0.into_boxed_slice()))
+    }
+}
+
+type NativeHandler = Box<dyn Fn(&str, V8Value, &[V8Value]) -> Result<V8Value, String> + Send + 'static>;
+
+/// Closures that can be turned into native JavaScript functions with
+/// [V8Value::new_typed_function]. Implemented for closures of up to eight
+/// arguments implementing [FromV8] that return a `Result` whose value
+/// implements [IntoV8] and whose error implements [Display].
+pub trait IntoV8Function<Args> {
+    fn into_handler(self) -> NativeHandler;
+}
+
+macro_rules! into_v8_function {
+    ($count:expr; $($arg:ident $var:ident: $index:tt),*) => {
+        impl<F, R, E, $($arg),*> IntoV8Function<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Result<R, E> + Send + 'static,
+            R: IntoV8,
+            E: Display,
+            $($arg: FromV8,)*
+        {
+            fn into_handler(self) -> NativeHandler {
+                Box::new(move |name, _this, args| {
+                    if args.len() > $count {
+                        return Err(format!("{} expects at most {} arguments, got {}", name, $count, args.len()));
+                    }
+                    let undefined = V8Value::undefined();
+                    $(
+                        let $var = match $arg::from_v8(args.get($index).unwrap_or(&undefined)) {
+                            Ok(value) => value,
+                            Err(error) => return Err(format!("{}: argument {}: {}", name, $index + 1, error)),
+                        };
+                    )*
+                    match self($($var),*) {
+                        Ok(value) => value.into_v8(),
+                        Err(error) => Err(error.to_string()),
+                    }
+                })
+            }
+        }
+    };
+}
+
+into_v8_function!(0;);
+into_v8_function!(1; A1 a1: 0);
+into_v8_function!(2; A1 a1: 0, A2 a2: 1);
+into_v8_function!(3; A1 a1: 0, A2 a2: 1, A3 a3: 2);
+into_v8_function!(4; A1 a1: 0, A2 a2: 1, A3 a3: 2, A4 a4: 3);
+into_v8_function!(5; A1 a1: 0, A2 a2: 1, A3 a3: 2, A4 a4: 3, A5 a5: 4);
+into_v8_function!(6; A1 a1: 0, A2 a2: 1, A3 a3: 2, A4 a4: 3, A5 a5: 4, A6 a6: 5);
+into_v8_function!(7; A1 a1: 0, A2 a2: 1, A3 a3: 2, A4 a4: 3, A5 a5: 4, A6 a6: 5, A7 a7: 6);
+into_v8_function!(8; A1 a1: 0, A2 a2: 1, A3 a3: 2, A4 a4: 3, A5 a5: 4, A6 a6: 5, A7 a7: 6, A8 a8: 7);
+
+impl V8Value {
+    /// Create a new V8Value object of type function that converts its arguments
+    /// with [FromV8], calls `handler` and converts the result with [IntoV8].
+    ///
+    /// Missing arguments are treated as `undefined`, so trailing `Option`
+    /// parameters may be omitted by the caller. Passing too many arguments or
+    /// arguments of the wrong type throws an `Error` describing the problem, as
+    /// does an `Err` returned by `handler`.
+    ///
+    /// This function has the same restrictions as [V8Value::new_function].
+    pub fn new_typed_function<Args>(name: &str, handler: impl IntoV8Function<Args>) -> Self {
+        V8Value::new_function_boxed(name, handler.into_handler())
+    }
+}
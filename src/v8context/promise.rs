@@ -0,0 +1,192 @@
+//! JavaScript Promises settled from Rust, and async Rust functions exposed to
+//! JavaScript.
+//!
+//! CEF can't create Promises itself, so [V8Context::new_promise] creates one in
+//! JavaScript and keeps its `resolve` and `reject` functions, together with the
+//! context, in a registry. A [PromiseResolver] can be sent to any thread; settling
+//! it posts a task to the context's task runner that calls the right function.
+//! Promises still pending when their context is released are rejected.
+
+use super::{IntoV8, V8Context, V8PropertyAttribute, V8Value};
+use crate::task::TaskRunner;
+use futures::future::{abortable, AbortHandle};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+type Settlement = Box<dyn FnOnce() -> Result<V8Value, String> + Send>;
+
+struct PendingPromise {
+    context: V8Context,
+    resolve: V8Value,
+    reject: V8Value,
+    /// Aborts the future backing a function registered with
+    /// [V8Context::register_async_fn].
+    abort: Option<AbortHandle>,
+}
+
+lazy_static::lazy_static! {
+    static ref PENDING: Mutex<HashMap<u64, PendingPromise>> = Mutex::new(HashMap::new());
+}
+
+static NEXT_PROMISE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Settles a Promise created with [V8Context::new_promise]. The resolver can be
+/// sent to and settled from any thread. Dropping it without settling rejects
+/// the Promise.
+pub struct PromiseResolver {
+    id: u64,
+    runner: TaskRunner,
+    settled: bool,
+}
+
+impl PromiseResolver {
+    /// Resolves the Promise with `value`, which is converted on the context's
+    /// thread.
+    pub fn resolve<T: IntoV8 + Send + 'static>(mut self, value: T) {
+        self.settle(Box::new(move || value.into_v8()));
+    }
+    /// Rejects the Promise with an `Error` carrying `message`.
+    pub fn reject(mut self, message: &str) {
+        let message = message.to_owned();
+        self.settle(Box::new(move || Err(message)));
+    }
+
+    fn settle(&mut self, settlement: Settlement) {
+        self.settled = true;
+        let id = self.id;
+        self.runner.post_task(move || settle(id, settlement));
+    }
+}
+
+impl Drop for PromiseResolver {
+    fn drop(&mut self) {
+        if !self.settled {
+            self.settle(Box::new(|| Err("The promise was dropped without being settled".to_owned())));
+        }
+    }
+}
+
+fn settle(id: u64, settlement: Settlement) {
+    let pending = PENDING.lock().remove(&id);
+    if let Some(pending) = pending {
+        pending.context.execute_in_context(|| match settlement() {
+            Ok(value) => pending.resolve.execute_function(None, &[value]),
+            Err(message) => pending.reject.execute_function(None, &[error_object(&message)]),
+        });
+    }
+}
+
+/// Creates an `Error` object. Must be called with a context entered.
+fn error_object(message: &str) -> V8Value {
+    V8Context::get_current()
+        .and_then(|context| context.get_global())
+        .and_then(|global| global.get_value_bykey("Error"))
+        .and_then(|error| error.execute_function(None, &[V8Value::from(message)]))
+        .unwrap_or_else(|| V8Value::from(message))
+}
+
+/// Rejects all Promises created in `context`, and stops the futures backing
+/// them. Called when the context is released.
+pub(crate) fn reject_pending_promises(context: &V8Context) {
+    let rejected = {
+        let mut pending = PENDING.lock();
+        let ids = pending.iter()
+            .filter(|(_, promise)| promise.context.is_same(context))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        ids.into_iter().filter_map(|id| pending.remove(&id)).collect::<Vec<_>>()
+    };
+    for promise in rejected {
+        if let Some(abort) = &promise.abort {
+            abort.abort();
+        }
+        promise.context.execute_in_context(|| {
+            promise.reject.execute_function(None, &[error_object("The context was released")])
+        });
+    }
+}
+
+impl V8Context {
+    /// Creates a new Promise in this context. Returns the Promise together with
+    /// a [PromiseResolver] that settles it, or None if the Promise couldn't be
+    /// created. Must be called on the context's thread.
+    pub fn new_promise(&self) -> Option<(V8Value, PromiseResolver)> {
+        let parts = self.eval(
+            "(function() {
+                let resolve, reject;
+                const promise = new Promise(function(res, rej) { resolve = res; reject = rej; });
+                return [promise, resolve, reject];
+            })()",
+            "",
+            0,
+        ).ok()?;
+        let (promise, resolve, reject) = self.execute_in_context(|| {
+            (parts.get_value_byindex(0), parts.get_value_byindex(1), parts.get_value_byindex(2))
+        })?;
+        let (promise, resolve, reject) = (promise?, resolve?, reject?);
+
+        let id = NEXT_PROMISE_ID.fetch_add(1, Ordering::Relaxed);
+        PENDING.lock().insert(id, PendingPromise {
+            context: self.clone(),
+            resolve,
+            reject,
+            abort: None,
+        });
+        Some((promise, PromiseResolver {
+            id,
+            runner: self.get_task_runner(),
+            settled: false,
+        }))
+    }
+
+    /// Registers a function named `name` on the global object that calls `f` and
+    /// returns a Promise for the result of the future it returns. The future is
+    /// polled on the context's task runner. An `Ok` value is converted with
+    /// [IntoV8] and resolves the Promise, an `Err` rejects it with an `Error`.
+    ///
+    /// `f` is called synchronously with the arguments, so it may inspect them
+    /// before returning the future; the future itself runs with no context
+    /// entered. Returns false if the function couldn't be registered.
+    ///
+    /// ```ignore
+    /// context.register_async_fn("fetchName", |args| {
+    ///     let id = args.get(0).and_then(V8Value::get_int_value);
+    ///     async move { ask_browser_for_name(id).await }
+    /// });
+    /// ```
+    pub fn register_async_fn<F, Fut, T, E>(&self, name: &str, f: F) -> bool
+    where
+        F: Fn(Vec<V8Value>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        T: IntoV8 + Send + 'static,
+        E: Display + Send + 'static,
+    {
+        self.execute_in_context(move || {
+            let function = V8Value::new_function(name, move |_, _, args| {
+                let context = V8Context::get_current().ok_or_else(|| "No current context".to_owned())?;
+                let (promise, resolver) = context.new_promise().ok_or_else(|| "Unable to create a promise".to_owned())?;
+                let (future, abort) = abortable(f(args.to_vec()));
+                if let Some(pending) = PENDING.lock().get_mut(&resolver.id) {
+                    pending.abort = Some(abort);
+                }
+                context.get_task_runner().spawn(async move {
+                    match future.await {
+                        Ok(Ok(value)) => resolver.resolve(value),
+                        Ok(Err(error)) => resolver.reject(&error.to_string()),
+                        // The context was released and the promise already rejected.
+                        Err(_) => (),
+                    }
+                });
+                Ok(promise)
+            });
+            self.get_global()
+                .map(|global| global.set_value_bykey(name, function, V8PropertyAttribute::READ_ONLY))
+                .unwrap_or(false)
+        }).unwrap_or(false)
+    }
+}
@@ -1,5 +1,11 @@
 pub mod iter;
+mod json;
+#[cfg(feature = "serde")]
+mod serialize;
 use self::iter::DictionaryValueKeysIter;
+pub use self::json::{parse_json, write_json, JsonError, JsonParserOptions};
+#[cfg(feature = "serde")]
+pub use self::serialize::{from_value, to_value, Error};
 use cef_sys::{
     cef_binary_value_create, cef_binary_value_t, cef_dictionary_value_create,
     cef_dictionary_value_t, cef_list_value_create, cef_list_value_t, cef_point_t, cef_range_t,
@@ -10,6 +16,7 @@ use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
     fmt,
+    io::{self, BufRead, Read, Seek, SeekFrom},
     marker::PhantomData,
 };
 
@@ -323,6 +330,16 @@ impl BinaryValue {
             ))
         }
     }
+    /// Creates a new object that is not owned by any other object from `data`.
+    /// CEF always keeps its own copy of binary data, so this copies `data` once
+    /// and frees it afterwards.
+    pub fn from_vec(data: Vec<u8>) -> Self {
+        Self::new(&data)
+    }
+    /// Returns a cursor that reads the data without copying all of it.
+    pub fn cursor(&self) -> BinaryValueCursor {
+        BinaryValueCursor::new(self.clone())
+    }
     /// Returns true if this object is valid. This object may become invalid if
     /// the underlying data is owned by another object (e.g. list or dictionary)
     /// and that other object is then modified or destroyed. Do not call any other
@@ -432,53 +449,128 @@ impl fmt::Debug for BinaryValue {
     }
 }
 
-// TODO: CREATE `BinaryValueCursor`
-// impl Read for BinaryValue {
-//     fn read(&self, buf: &mut [u8]) -> std::io::Result<usize> {
-//         self.as_ref()
-//             .get_data
-//             .and_then(|get_data| {
-//                 Some(unsafe {
-//                     get_data(
-//                         self.0,
-//                         buf.as_mut_ptr() as *mut std::ffi::c_void,
-//                         buf.len(),
-//                         self.1,
-//                     )
-//                 })
-//             })
-//             .and_then(|result| {
-//                 self.1 += result;
-//                 Some(result)
-//             })
-//             .ok_or_else(|| {
-//                 std::io::Error::new(
-//                     std::io::ErrorKind::InvalidData,
-//                     "cef_binary_value_t is invalid",
-//                 )
-//             })
-//     }
-// }
+/// A [Read], [BufRead] and [Seek] implementation over a [BinaryValue] that
+/// reads the data at the current offset instead of copying the whole buffer.
+/// Reads larger than the internal buffer go straight to the value.
+pub struct BinaryValueCursor {
+    value: BinaryValue,
+    position: u64,
+    buffer: Box<[u8]>,
+    /// The range of `buffer` holding the data at `position`.
+    filled: std::ops::Range<usize>,
+}
+
+impl BinaryValueCursor {
+    const BUFFER_SIZE: usize = 8 * 1024;
+
+    pub fn new(value: BinaryValue) -> Self {
+        BinaryValueCursor {
+            value,
+            position: 0,
+            buffer: Box::new([]),
+            filled: 0..0,
+        }
+    }
+    /// Returns the current position of this cursor.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+    /// Sets the position of this cursor.
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+        self.filled = 0..0;
+    }
+    pub fn get_ref(&self) -> &BinaryValue {
+        &self.value
+    }
+    pub fn into_inner(self) -> BinaryValue {
+        self.value
+    }
+
+    /// Copies the data at `offset` into `buf`, returning how many bytes were
+    /// copied.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let get_data = self.value.0.get_data.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "cef_binary_value_t is invalid")
+        })?;
+        let offset = match usize::try_from(offset) {
+            Ok(offset) if offset < self.value.len() => offset,
+            _ => return Ok(0),
+        };
+        Ok(unsafe {
+            get_data(
+                self.value.as_ptr(),
+                buf.as_mut_ptr() as *mut std::ffi::c_void,
+                buf.len(),
+                offset,
+            )
+        })
+    }
+}
+
+impl Read for BinaryValueCursor {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.filled.is_empty() && buf.len() >= Self::BUFFER_SIZE {
+            let read = self.read_at(self.position, buf)?;
+            self.position += read as u64;
+            return Ok(read);
+        }
+        let read = {
+            let mut available = self.fill_buf()?;
+            available.read(buf)?
+        };
+        self.consume(read);
+        Ok(read)
+    }
+}
 
-// impl std::io::Seek for BinaryValue {
-//     fn seek(&self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
-//         self.1 = match pos {
-//             std::io::SeekFrom::Start(offset) => usize::try_from(offset),
-//             std::io::SeekFrom::Current(offset) => usize::try_from(self.1 as i64 + offset),
-//             std::io::SeekFrom::End(offset) => {
-//                 if offset > self.1 as i64 {
-//                     Ok(0)
-//                 } else {
-//                     Ok((self.1 as i64 - offset) as usize)
-//                 }
-//             }
-//         }
-//         .map_err(|err| {
-//             std::io::Error::new(std::io::ErrorKind::InvalidInput, "offset is out of range")
-//         })?;
-//         Ok(self.1 as u64)
-//     }
-// }
+impl BufRead for BinaryValueCursor {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.filled.is_empty() {
+            if self.buffer.is_empty() {
+                self.buffer = vec![0; Self::BUFFER_SIZE].into_boxed_slice();
+            }
+            let mut buffer = std::mem::take(&mut self.buffer);
+            let read = self.read_at(self.position, &mut buffer);
+            self.buffer = buffer;
+            self.filled = 0..read?;
+        }
+        Ok(&self.buffer[self.filled.clone()])
+    }
+    fn consume(&mut self, amount: usize) {
+        let amount = amount.min(self.filled.len());
+        self.filled.start += amount;
+        self.position += amount as u64;
+    }
+}
+
+impl Seek for BinaryValueCursor {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => {
+                self.set_position(offset);
+                return Ok(offset);
+            }
+            SeekFrom::End(offset) => (self.value.len() as u64, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        let position = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.wrapping_neg() as u64)
+        };
+        match position {
+            Some(position) => {
+                self.set_position(position);
+                Ok(position)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
 
 impl PartialEq for BinaryValue {
     /// Returns true if this object and `that` object have an equivalent
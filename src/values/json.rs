@@ -0,0 +1,171 @@
+//! JSON parsing and writing with CEF's JSON implementation.
+//!
+//! JSON objects map to [DictionaryValue]s and arrays to [ListValue]s. Numbers
+//! that fit into an `i32` are parsed as [StoredValue::Int], all others as
+//! [StoredValue::Double].
+
+use super::{StoredValue, Value};
+#[cfg(feature = "serde_json")]
+use super::{DictionaryValue, ListValue};
+use crate::string::CefString;
+use bitflags::bitflags;
+use cef_sys::{
+    cef_json_parser_options_t, cef_json_writer_options_t, cef_parse_jsonand_return_error,
+    cef_write_json,
+};
+use std::{convert::TryFrom, fmt};
+
+bitflags! {
+    /// Options for [parse_json]. CEF parses strictly according to RFC 8259 by
+    /// default; it doesn't expose Chromium's options for tolerating invalid
+    /// escapes or control characters, so those are always rejected.
+    #[derive(Default)]
+    pub struct JsonParserOptions: crate::CEnumType {
+        /// Allows commas after the last element of objects and arrays.
+        const ALLOW_TRAILING_COMMAS = cef_json_parser_options_t::JSON_PARSER_ALLOW_TRAILING_COMMAS.0;
+    }
+}
+
+/// The error returned by [parse_json].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    /// The description of the error, without the position.
+    pub message: String,
+    /// The 1-based line of the error, or 0 if CEF didn't report it.
+    pub line: usize,
+    /// The 1-based column of the error, or 0 if CEF didn't report it.
+    pub column: usize,
+}
+
+impl JsonError {
+    /// Splits the messages produced by Chromium's JSON reader, which look like
+    /// `Line: 1, column: 5, Unexpected token.`
+    fn parse(error: &str) -> JsonError {
+        let position = error.strip_prefix("Line: ").and_then(|rest| {
+            let (line, rest) = rest.split_at(rest.find(',')?);
+            let rest = rest.strip_prefix(", column: ")?;
+            let (column, rest) = rest.split_at(rest.find(',')?);
+            Some((line.parse().ok()?, column.parse().ok()?, rest.trim_start_matches(',').trim_start()))
+        });
+        match position {
+            Some((line, column, message)) => JsonError {
+                message: message.to_owned(),
+                line,
+                column,
+            },
+            None => JsonError {
+                message: if error.is_empty() { "Invalid JSON".to_owned() } else { error.to_owned() },
+                line: 0,
+                column: 0,
+            },
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{} at line {} column {}", self.message, self.line, self.column)
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// Parses `json` into a [StoredValue].
+pub fn parse_json(json: &str, options: JsonParserOptions) -> Result<StoredValue, JsonError> {
+    let mut error = CefString::null();
+    let value = unsafe {
+        Value::from_ptr(cef_parse_jsonand_return_error(
+            CefString::new(json).as_ptr(),
+            cef_json_parser_options_t(options.bits()),
+            error.as_ptr_mut(),
+        ))
+    };
+    match value {
+        Some(value) => Ok(value.into()),
+        None => Err(JsonError::parse(&String::from(error))),
+    }
+}
+
+/// Writes `value` as JSON. Binary values can't be represented in JSON and are
+/// omitted. If `pretty` is true, the output is indented and ends with a line
+/// break.
+pub fn write_json(value: &StoredValue, pretty: bool) -> String {
+    let mut options = cef_json_writer_options_t::JSON_WRITER_OMIT_BINARY_VALUES.0;
+    if pretty {
+        options |= cef_json_writer_options_t::JSON_WRITER_PRETTY_PRINT.0;
+    }
+    let value = match Value::try_from(value.clone()) {
+        Ok(value) => value,
+        Err(_) => return String::new(),
+    };
+    unsafe {
+        CefString::from_userfree(cef_write_json(value.as_ptr(), cef_json_writer_options_t(options)))
+            .map(String::from)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl From<serde_json::Value> for StoredValue {
+    /// Integers outside the range of `i32` become [StoredValue::Double]s, as they
+    /// do in [parse_json].
+    fn from(value: serde_json::Value) -> StoredValue {
+        match value {
+            serde_json::Value::Null => StoredValue::Null,
+            serde_json::Value::Bool(b) => StoredValue::Bool(b),
+            serde_json::Value::Number(n) => n
+                .as_i64()
+                .and_then(|i| i32::try_from(i).ok())
+                .map(StoredValue::Int)
+                .unwrap_or_else(|| StoredValue::Double(n.as_f64().unwrap_or(0.0))),
+            serde_json::Value::String(s) => StoredValue::String(s),
+            serde_json::Value::Array(array) => {
+                let list = ListValue::new();
+                for (index, value) in array.into_iter().enumerate() {
+                    list.set_value_inner(index, Value::try_from(StoredValue::from(value)).unwrap());
+                }
+                StoredValue::List(list)
+            }
+            serde_json::Value::Object(object) => {
+                let dictionary = DictionaryValue::new();
+                for (key, value) in object {
+                    dictionary.insert(&key, value.into());
+                }
+                StoredValue::Dictionary(dictionary)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl TryFrom<StoredValue> for serde_json::Value {
+    type Error = &'static str;
+
+    /// Fails for binary values and for doubles that are infinite or NaN, since
+    /// JSON can't represent them.
+    fn try_from(value: StoredValue) -> Result<Self, Self::Error> {
+        Ok(match value {
+            StoredValue::Invalid | StoredValue::Null => serde_json::Value::Null,
+            StoredValue::Bool(b) => serde_json::Value::Bool(b),
+            StoredValue::Int(i) => serde_json::Value::from(i),
+            StoredValue::Double(f) => serde_json::Number::from_f64(f)
+                .map(serde_json::Value::Number)
+                .ok_or("Unable to represent a non-finite number in JSON")?,
+            StoredValue::String(s) => serde_json::Value::String(s),
+            StoredValue::Binary(_) => return Err("Unable to represent a binary value in JSON"),
+            StoredValue::List(list) => serde_json::Value::Array(
+                list.into_iter().map(serde_json::Value::try_from).collect::<Result<_, _>>()?,
+            ),
+            StoredValue::Dictionary(dictionary) => serde_json::Value::Object(
+                dictionary
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, serde_json::Value::try_from(value)?)))
+                    .collect::<Result<_, Self::Error>>()?,
+            ),
+        })
+    }
+}
@@ -0,0 +1,777 @@
+//! Conversion between Rust types implementing serde's traits and [StoredValue].
+//!
+//! Structs and maps become [DictionaryValue]s, sequences and tuples become
+//! [ListValue]s and byte buffers become [BinaryValue]s. Enums use serde's default
+//! externally tagged representation: unit variants are stored as strings, all
+//! other variants as a dictionary with a single key naming the variant. Since CEF
+//! only stores 32 bit integers, integers outside the range of `i32` fail with
+//! [Error::IntegerOverflow] instead of being truncated.
+
+use super::{BinaryValue, DictionaryValue, ListValue, StoredValue, Value};
+use serde::{
+    de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer, StringDeserializer},
+        DeserializeOwned, IntoDeserializer, Visitor,
+    },
+    forward_to_deserialize_any,
+    ser::{self, Impossible, Serialize},
+};
+use std::{convert::TryFrom, fmt};
+
+/// Errors that can occur in [to_value] and [from_value].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// An integer doesn't fit into the 32 bit integers CEF stores.
+    IntegerOverflow(i128),
+    /// A map key is not a string, a character or an integer.
+    KeyMustBeAString,
+    /// A custom error reported by a `Serialize` or `Deserialize` implementation.
+    Message(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::IntegerOverflow(value) => write!(f, "integer {} does not fit into a 32 bit CEF integer", value),
+            Error::KeyMustBeAString => write!(f, "map keys must be strings"),
+            Error::Message(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+/// Converts `value` to a [StoredValue].
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<StoredValue, Error> {
+    value.serialize(Serializer)
+}
+
+/// Converts a [StoredValue] to a `T`.
+pub fn from_value<T: DeserializeOwned>(value: StoredValue) -> Result<T, Error> {
+    T::deserialize(value)
+}
+
+fn int<T: Into<i128> + Copy>(value: T) -> Result<StoredValue, Error> {
+    let wide = value.into();
+    i32::try_from(wide)
+        .map(StoredValue::Int)
+        .map_err(|_| Error::IntegerOverflow(wide))
+}
+
+fn push(list: &ListValue, value: StoredValue) {
+    list.set_value_inner(list.len(), Value::try_from(value).unwrap());
+}
+
+fn variant(name: &str, value: StoredValue) -> StoredValue {
+    let dictionary = DictionaryValue::new();
+    dictionary.insert(name, value);
+    StoredValue::Dictionary(dictionary)
+}
+
+struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = StoredValue;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeDictionary;
+    type SerializeStruct = SerializeDictionary;
+    type SerializeStructVariant = SerializeDictionary;
+
+    fn serialize_bool(self, v: bool) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Bool(v))
+    }
+    fn serialize_i8(self, v: i8) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Int(v.into()))
+    }
+    fn serialize_i16(self, v: i16) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Int(v.into()))
+    }
+    fn serialize_i32(self, v: i32) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Int(v))
+    }
+    fn serialize_i64(self, v: i64) -> Result<StoredValue, Error> {
+        int(v)
+    }
+    fn serialize_i128(self, v: i128) -> Result<StoredValue, Error> {
+        int(v)
+    }
+    fn serialize_u8(self, v: u8) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Int(v.into()))
+    }
+    fn serialize_u16(self, v: u16) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Int(v.into()))
+    }
+    fn serialize_u32(self, v: u32) -> Result<StoredValue, Error> {
+        int(v)
+    }
+    fn serialize_u64(self, v: u64) -> Result<StoredValue, Error> {
+        int(v)
+    }
+    fn serialize_f32(self, v: f32) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Double(v.into()))
+    }
+    fn serialize_f64(self, v: f64) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Double(v))
+    }
+    fn serialize_char(self, v: char) -> Result<StoredValue, Error> {
+        Ok(StoredValue::String(v.to_string()))
+    }
+    fn serialize_str(self, v: &str) -> Result<StoredValue, Error> {
+        Ok(StoredValue::String(v.to_owned()))
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Binary(BinaryValue::new(v)))
+    }
+    fn serialize_none(self) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Null)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<StoredValue, Error> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Null)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<StoredValue, Error> {
+        Ok(StoredValue::Null)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<StoredValue, Error> {
+        Ok(StoredValue::String(variant.to_owned()))
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<StoredValue, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant_name: &'static str,
+        value: &T,
+    ) -> Result<StoredValue, Error> {
+        Ok(variant(variant_name, value.serialize(self)?))
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SerializeList, Error> {
+        Ok(SerializeList {
+            list: ListValue::new(),
+            variant: None,
+        })
+    }
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeList, Error> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeList, Error> {
+        Ok(SerializeList {
+            list: ListValue::new(),
+            variant: Some(variant),
+        })
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeDictionary, Error> {
+        Ok(SerializeDictionary {
+            dictionary: DictionaryValue::new(),
+            next_key: None,
+            variant: None,
+        })
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeDictionary, Error> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeDictionary, Error> {
+        Ok(SerializeDictionary {
+            dictionary: DictionaryValue::new(),
+            next_key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+struct SerializeList {
+    list: ListValue,
+    variant: Option<&'static str>,
+}
+
+impl SerializeList {
+    fn finish(self) -> Result<StoredValue, Error> {
+        let list = StoredValue::List(self.list);
+        Ok(match self.variant {
+            Some(name) => variant(name, list),
+            None => list,
+        })
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = StoredValue;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        push(&self.list, value.serialize(Serializer)?);
+        Ok(())
+    }
+    fn end(self) -> Result<StoredValue, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = StoredValue;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<StoredValue, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = StoredValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<StoredValue, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = StoredValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+    fn end(self) -> Result<StoredValue, Error> {
+        self.finish()
+    }
+}
+
+struct SerializeDictionary {
+    dictionary: DictionaryValue,
+    next_key: Option<String>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeDictionary {
+    fn finish(self) -> Result<StoredValue, Error> {
+        let dictionary = StoredValue::Dictionary(self.dictionary);
+        Ok(match self.variant {
+            Some(name) => variant(name, dictionary),
+            None => dictionary,
+        })
+    }
+}
+
+impl ser::SerializeMap for SerializeDictionary {
+    type Ok = StoredValue;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.next_key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.next_key.take()
+            .ok_or_else(|| Error::Message("serialize_value called before serialize_key".to_owned()))?;
+        self.dictionary.insert(&key, value.serialize(Serializer)?);
+        Ok(())
+    }
+    fn end(self) -> Result<StoredValue, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeDictionary {
+    type Ok = StoredValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.dictionary.insert(key, value.serialize(Serializer)?);
+        Ok(())
+    }
+    fn end(self) -> Result<StoredValue, Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeDictionary {
+    type Ok = StoredValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+    fn end(self) -> Result<StoredValue, Error> {
+        self.finish()
+    }
+}
+
+/// Serializes map keys. Strings and characters are used as they are, integers
+/// are converted to their decimal representation.
+struct KeySerializer;
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<String, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_i8(self, v: i8) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+    fn serialize_i16(self, v: i16) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+    fn serialize_i32(self, v: i32) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+    fn serialize_i64(self, v: i64) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+    fn serialize_u8(self, v: u8) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+    fn serialize_u16(self, v: u16) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+    fn serialize_u32(self, v: u32) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+    fn serialize_u64(self, v: u64) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+    fn serialize_f32(self, _v: f32) -> Result<String, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_f64(self, _v: f64) -> Result<String, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_char(self, v: char) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+    fn serialize_str(self, v: &str) -> Result<String, Error> {
+        Ok(v.to_owned())
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_none(self) -> Result<String, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<String, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_unit(self) -> Result<String, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, Error> {
+        Ok(variant.to_owned())
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(Error::KeyMustBeAString)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for StoredValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for StoredValue {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            StoredValue::Invalid | StoredValue::Null => visitor.visit_unit(),
+            StoredValue::Bool(v) => visitor.visit_bool(v),
+            StoredValue::Int(v) => visitor.visit_i32(v),
+            StoredValue::Double(v) => visitor.visit_f64(v),
+            StoredValue::String(v) => visitor.visit_string(v),
+            StoredValue::Binary(v) => visitor.visit_byte_buf(v.into()),
+            StoredValue::Dictionary(v) => {
+                let entries = (&v).into_iter().map(|(key, value)| (MapKey(key), value)).collect::<Vec<_>>();
+                let mut map = MapDeserializer::new(entries.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            StoredValue::List(v) => {
+                let mut seq = SeqDeserializer::new(v.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            StoredValue::Invalid | StoredValue::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            StoredValue::String(variant) => {
+                let variant: StringDeserializer<Error> = variant.into_deserializer();
+                visitor.visit_enum(variant)
+            }
+            StoredValue::Dictionary(dictionary) => {
+                let mut entries = (&dictionary).into_iter();
+                match (entries.next(), entries.next()) {
+                    (Some((variant, value)), None) => visitor.visit_enum(Enum { variant, value }),
+                    _ => Err(de::Error::invalid_value(
+                        de::Unexpected::Map,
+                        &"a dictionary with a single key",
+                    )),
+                }
+            }
+            other => Err(de::Error::invalid_type(other.unexpected(), &"a string or a dictionary")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Deserializes a dictionary key. Integer keys are stored as strings by
+/// [KeySerializer], so they are parsed back when an integer is expected.
+struct MapKey(String);
+
+impl<'de> IntoDeserializer<'de, Error> for MapKey {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident),* $(,)?) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.0.parse() {
+                Ok(value) => visitor.$visit(value),
+                Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(&self.0), &visitor)),
+            }
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for MapKey {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let variant: StringDeserializer<Error> = self.0.into_deserializer();
+        visitor.visit_enum(variant)
+    }
+
+    forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf unit unit_struct
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl StoredValue {
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match self {
+            StoredValue::Invalid | StoredValue::Null => de::Unexpected::Unit,
+            StoredValue::Bool(v) => de::Unexpected::Bool(*v),
+            StoredValue::Int(v) => de::Unexpected::Signed((*v).into()),
+            StoredValue::Double(v) => de::Unexpected::Float(*v),
+            StoredValue::String(v) => de::Unexpected::Str(v),
+            StoredValue::Binary(_) => de::Unexpected::Bytes(&[]),
+            StoredValue::Dictionary(_) => de::Unexpected::Map,
+            StoredValue::List(_) => de::Unexpected::Seq,
+        }
+    }
+}
+
+struct Enum {
+    variant: String,
+    value: StoredValue,
+}
+
+impl<'de> de::EnumAccess<'de> for Enum {
+    type Error = Error;
+    type Variant = StoredValue;
+
+    fn variant_seed<S: de::DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, StoredValue), Error> {
+        let variant: StringDeserializer<Error> = self.variant.into_deserializer();
+        Ok((seed.deserialize(variant)?, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for StoredValue {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self {
+            StoredValue::Invalid | StoredValue::Null => Ok(()),
+            other => Err(de::Error::invalid_type(other.unexpected(), &"unit variant")),
+        }
+    }
+    fn newtype_variant_seed<S: de::DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, Error> {
+        seed.deserialize(self)
+    }
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        name: String,
+        zoom: f64,
+        enabled: bool,
+        tabs: Vec<u16>,
+        homepage: Option<String>,
+        proxy: Option<String>,
+        mode: Mode,
+        limits: HashMap<u8, i64>,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+    enum Mode {
+        Light,
+        Dark,
+        Custom(String),
+        Scaled { factor: i32 },
+    }
+
+    #[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+    struct TabId(u32);
+
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        from_value(to_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn round_trips_integer_keys() {
+        let map: HashMap<i32, String> = vec![(-1, "minus one".to_owned()), (0, "zero".to_owned()), (42, "answer".to_owned())]
+            .into_iter()
+            .collect();
+        assert_eq!(round_trip(&map), map);
+
+        let map: BTreeMap<u64, bool> = vec![(0, false), (u64::MAX, true)].into_iter().collect();
+        assert_eq!(round_trip(&map), map);
+
+        let map: BTreeMap<TabId, i32> = vec![(TabId(3), 1), (TabId(7), 2)].into_iter().collect();
+        assert_eq!(round_trip(&map), map);
+    }
+
+    #[test]
+    fn round_trips_other_keys() {
+        let map: BTreeMap<String, i32> = vec![("12".to_owned(), 1), ("a".to_owned(), 2)].into_iter().collect();
+        assert_eq!(round_trip(&map), map);
+
+        let map: BTreeMap<char, i32> = vec![('x', 1), ('7', 2)].into_iter().collect();
+        assert_eq!(round_trip(&map), map);
+
+        let map: BTreeMap<Mode, i32> = vec![(Mode::Light, 1), (Mode::Dark, 2)].into_iter().collect();
+        assert_eq!(round_trip(&map), map);
+    }
+
+    #[test]
+    fn round_trips_structs_and_enums() {
+        let settings = Settings {
+            name: "default".to_owned(),
+            zoom: 1.25,
+            enabled: true,
+            tabs: vec![1, 2, 3],
+            homepage: Some("https://example.com/".to_owned()),
+            proxy: None,
+            mode: Mode::Scaled { factor: 2 },
+            limits: vec![(1, 10), (2, -20)].into_iter().collect(),
+        };
+        assert_eq!(round_trip(&settings), settings);
+        assert_eq!(round_trip(&Mode::Custom("sepia".to_owned())), Mode::Custom("sepia".to_owned()));
+        assert_eq!(round_trip(&Mode::Dark), Mode::Dark);
+        assert_eq!(round_trip(&(1, "two".to_owned(), 3.5)), (1, "two".to_owned(), 3.5));
+    }
+
+    #[test]
+    fn rejects_unparsable_keys() {
+        let map: BTreeMap<String, i32> = vec![("one".to_owned(), 1)].into_iter().collect();
+        assert!(from_value::<HashMap<i32, i32>>(to_value(&map).unwrap()).is_err());
+        let map: BTreeMap<String, i32> = vec![("300".to_owned(), 1)].into_iter().collect();
+        assert!(from_value::<HashMap<u8, i32>>(to_value(&map).unwrap()).is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_values() {
+        assert_eq!(to_value(&(i64::from(i32::MAX) + 1)).err(), Some(Error::IntegerOverflow(2_147_483_648)));
+        let map: HashMap<bool, i32> = vec![(true, 1)].into_iter().collect();
+        assert_eq!(to_value(&map).err(), Some(Error::KeyMustBeAString));
+    }
+}
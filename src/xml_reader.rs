@@ -0,0 +1,284 @@
+//! A pull parser for XML documents, backed by CEF's libxml reader.
+//!
+//! ```ignore
+//! let stream = StreamReader::from_file("subscriptions.opml").unwrap();
+//! let reader = XmlReader::new(stream, XmlEncoding::None, "file:///subscriptions.opml").unwrap();
+//! for node in reader {
+//!     match node?.event {
+//!         XmlEvent::StartElement { name, attributes } if name.local_name == "outline" => {
+//!             let url = attributes.iter().find(|attribute| attribute.name.local_name == "xmlUrl");
+//!             // ...
+//!         }
+//!         _ => {}
+//!     }
+//! }
+//! ```
+
+use crate::{string::CefString, stream::StreamReader};
+use cef_sys::{
+    cef_string_userfree_t, cef_xml_encoding_type_t, cef_xml_node_type_t, cef_xml_reader_create,
+    cef_xml_reader_t,
+};
+use std::{error::Error, fmt, marker::PhantomData};
+
+/// Supported XML encoding types. The parser supports ASCII, ISO-8859-1, and
+/// UTF16 (LE and BE) by default. All other types must be translated to UTF8
+/// before being passed to the parser. If a BOM is detected and the correct
+/// decoder is available then that decoder will be used automatically.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum XmlEncoding {
+    None = cef_xml_encoding_type_t::XML_ENCODING_NONE as isize,
+    UTF8 = cef_xml_encoding_type_t::XML_ENCODING_UTF8 as isize,
+    UTF16LE = cef_xml_encoding_type_t::XML_ENCODING_UTF16LE as isize,
+    UTF16BE = cef_xml_encoding_type_t::XML_ENCODING_UTF16BE as isize,
+    ASCII = cef_xml_encoding_type_t::XML_ENCODING_ASCII as isize,
+}
+
+/// The name of an element or attribute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XmlName {
+    pub local_name: String,
+    /// The namespace prefix, or an empty string if there is none.
+    pub prefix: String,
+    /// The URI of the namespace the name belongs to, or an empty string if it
+    /// isn't in a namespace.
+    pub namespace_uri: String,
+}
+
+impl XmlName {
+    /// Returns the name as written in the document, with its prefix.
+    pub fn qualified_name(&self) -> String {
+        if self.prefix.is_empty() {
+            self.local_name.clone()
+        } else {
+            format!("{}:{}", self.prefix, self.local_name)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XmlAttribute {
+    pub name: XmlName,
+    pub value: String,
+}
+
+/// The nodes of an XML document, in document order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum XmlEvent {
+    /// An element start tag. Namespace declarations are included in the
+    /// attributes.
+    StartElement {
+        name: XmlName,
+        attributes: Vec<XmlAttribute>,
+    },
+    /// An element end tag. Empty elements like `<br/>` are reported as a start
+    /// tag followed by an end tag.
+    EndElement { name: XmlName },
+    /// Character data, with entities resolved.
+    Text(String),
+    /// Whitespace between markup.
+    Whitespace(String),
+    /// The contents of a CDATA section.
+    CData(String),
+    Comment(String),
+    ProcessingInstruction { target: String, data: String },
+    /// A document type declaration, with the name of the root element.
+    DocumentType { name: String },
+    /// A reference to an entity that wasn't resolved.
+    EntityReference { name: String },
+}
+
+/// An [XmlEvent] and where in the document it was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XmlNode {
+    pub event: XmlEvent,
+    /// The depth of the node, with the root element at depth 0.
+    pub depth: usize,
+    /// The line number of the node in the document, starting at 1.
+    pub line_number: usize,
+}
+
+/// An error found while parsing an XML document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XmlError {
+    pub message: String,
+    pub line_number: usize,
+}
+
+impl fmt::Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "XML parse error on line {}: {}", self.line_number, self.message.trim_end())
+    }
+}
+
+impl Error for XmlError {}
+
+fn userfree_string(value: cef_string_userfree_t) -> String {
+    unsafe { CefString::from_userfree(value) }.map(String::from).unwrap_or_default()
+}
+
+ref_counted_ptr! {
+    struct XmlReaderPtr(*mut cef_xml_reader_t);
+}
+
+/// Reads an XML document from a [StreamReader] as an [Iterator] of [XmlNode]s.
+///
+/// The iterator ends after the end of the document or the first parse error.
+/// The reader must only be used on the thread that created it.
+pub struct XmlReader {
+    reader: XmlReaderPtr,
+    /// The end of an empty element, to be returned by the next call to `next`.
+    pending_end: Option<XmlNode>,
+    done: bool,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl XmlReader {
+    /// Create a new reader for `stream`. `encoding` is the encoding of the
+    /// document, or [XmlEncoding::None] to detect it, and `uri` is the base URI
+    /// of the document. Returns None if the reader can't be created.
+    pub fn new(stream: StreamReader, encoding: XmlEncoding, uri: &str) -> Option<XmlReader> {
+        let reader = unsafe {
+            XmlReaderPtr::from_ptr(cef_xml_reader_create(
+                stream.into_raw(),
+                encoding as cef_xml_encoding_type_t::Type,
+                CefString::new(uri).as_ptr(),
+            ))
+        }?;
+        Some(XmlReader {
+            reader,
+            pending_end: None,
+            done: false,
+            _not_send: PhantomData,
+        })
+    }
+
+    fn name(&self) -> XmlName {
+        unsafe {
+            XmlName {
+                local_name: userfree_string(self.reader.0.get_local_name.unwrap()(self.reader.as_ptr())),
+                prefix: userfree_string(self.reader.0.get_prefix.unwrap()(self.reader.as_ptr())),
+                namespace_uri: userfree_string(self.reader.0.get_namespace_uri.unwrap()(self.reader.as_ptr())),
+            }
+        }
+    }
+    fn qualified_name(&self) -> String {
+        unsafe { userfree_string(self.reader.0.get_qualified_name.unwrap()(self.reader.as_ptr())) }
+    }
+    fn value(&self) -> String {
+        unsafe { userfree_string(self.reader.0.get_value.unwrap()(self.reader.as_ptr())) }
+    }
+    fn attributes(&self) -> Vec<XmlAttribute> {
+        let mut attributes = Vec::new();
+        unsafe {
+            if self.reader.0.move_to_first_attribute.unwrap()(self.reader.as_ptr()) == 0 {
+                return attributes;
+            }
+            loop {
+                attributes.push(XmlAttribute {
+                    name: self.name(),
+                    value: self.value(),
+                });
+                if self.reader.0.move_to_next_attribute.unwrap()(self.reader.as_ptr()) == 0 {
+                    break;
+                }
+            }
+            self.reader.0.move_to_carrying_element.unwrap()(self.reader.as_ptr());
+        }
+        attributes
+    }
+    fn is_empty_element(&self) -> bool {
+        unsafe { self.reader.0.is_empty_element.unwrap()(self.reader.as_ptr()) != 0 }
+    }
+
+    /// Returns the depth of the current node.
+    pub fn depth(&self) -> usize {
+        unsafe { self.reader.0.get_depth.unwrap()(self.reader.as_ptr()).max(0) as usize }
+    }
+    /// Returns the line number of the current node.
+    pub fn line_number(&self) -> usize {
+        unsafe { self.reader.0.get_line_number.unwrap()(self.reader.as_ptr()).max(0) as usize }
+    }
+
+    fn error(&self) -> Option<XmlError> {
+        unsafe {
+            if self.reader.0.has_error.unwrap()(self.reader.as_ptr()) == 0 {
+                return None;
+            }
+            Some(XmlError {
+                message: userfree_string(self.reader.0.get_error.unwrap()(self.reader.as_ptr())),
+                line_number: self.line_number(),
+            })
+        }
+    }
+
+    fn event(&self) -> Option<XmlEvent> {
+        let node_type = unsafe { self.reader.0.get_type.unwrap()(self.reader.as_ptr()) };
+        Some(match node_type {
+            cef_xml_node_type_t::XML_NODE_ELEMENT_START => XmlEvent::StartElement {
+                name: self.name(),
+                attributes: self.attributes(),
+            },
+            cef_xml_node_type_t::XML_NODE_ELEMENT_END => XmlEvent::EndElement { name: self.name() },
+            cef_xml_node_type_t::XML_NODE_TEXT => XmlEvent::Text(self.value()),
+            cef_xml_node_type_t::XML_NODE_WHITESPACE => XmlEvent::Whitespace(self.value()),
+            cef_xml_node_type_t::XML_NODE_CDATA => XmlEvent::CData(self.value()),
+            cef_xml_node_type_t::XML_NODE_COMMENT => XmlEvent::Comment(self.value()),
+            cef_xml_node_type_t::XML_NODE_PROCESSING_INSTRUCTION => XmlEvent::ProcessingInstruction {
+                target: self.qualified_name(),
+                data: self.value(),
+            },
+            cef_xml_node_type_t::XML_NODE_DOCUMENT_TYPE => XmlEvent::DocumentType { name: self.qualified_name() },
+            cef_xml_node_type_t::XML_NODE_ENTITY_REFERENCE => XmlEvent::EntityReference { name: self.qualified_name() },
+            _ => return None,
+        })
+    }
+}
+
+impl Iterator for XmlReader {
+    type Item = Result<XmlNode, XmlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(end) = self.pending_end.take() {
+            return Some(Ok(end));
+        }
+        while !self.done {
+            let moved = unsafe { self.reader.0.move_to_next_node.unwrap()(self.reader.as_ptr()) != 0 };
+            if let Some(error) = self.error() {
+                self.done = true;
+                return Some(Err(error));
+            }
+            if !moved {
+                self.done = true;
+                break;
+            }
+            if let Some(event) = self.event() {
+                let node = XmlNode {
+                    depth: self.depth(),
+                    line_number: self.line_number(),
+                    event,
+                };
+                if let XmlEvent::StartElement { name, .. } = &node.event {
+                    if self.is_empty_element() {
+                        self.pending_end = Some(XmlNode {
+                            event: XmlEvent::EndElement { name: name.clone() },
+                            ..node.clone()
+                        });
+                    }
+                }
+                return Some(Ok(node));
+            }
+        }
+        None
+    }
+}
+
+impl Drop for XmlReader {
+    /// Closes the document on the thread the reader was used on.
+    fn drop(&mut self) {
+        if let Some(close) = self.reader.0.close {
+            unsafe { close(self.reader.as_ptr()) };
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Reading files from zip archives without extracting them.
+//!
+//! ```ignore
+//! let mut zip = ZipReader::new(StreamReader::from_file("content.zip").unwrap()).unwrap();
+//! for entry in zip.entries() {
+//!     println!("{} ({} bytes)", entry.name, entry.size);
+//! }
+//! if zip.move_to_file("manifest.json", false) && zip.open_file(None) {
+//!     let mut manifest = String::new();
+//!     zip.read_to_string(&mut manifest)?;
+//!     zip.close_file();
+//! }
+//! ```
+
+use crate::{stream::StreamReader, string::CefString, values::cef_time_to_date_time};
+use cef_sys::{cef_zip_reader_create, cef_zip_reader_t};
+use chrono::{DateTime, Utc};
+use std::{
+    ffi::c_void,
+    io::{self, Read},
+    marker::PhantomData,
+    ptr::null,
+};
+
+ref_counted_ptr! {
+    struct ZipReaderPtr(*mut cef_zip_reader_t);
+}
+
+/// A file in a zip archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZipEntry {
+    /// The path of the file in the archive, with `/` as separator.
+    pub name: String,
+    /// The uncompressed size of the file.
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// Reads a zip archive from a [StreamReader]. The reader points at one file of
+/// the archive at a time, whose contents can be read with [Read] after
+/// [ZipReader::open_file].
+///
+/// The reader must only be used on the thread that created it.
+pub struct ZipReader {
+    reader: ZipReaderPtr,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ZipReader {
+    /// Create a new reader for the archive in `stream`. Returns None if it isn't
+    /// a zip archive.
+    pub fn new(stream: StreamReader) -> Option<ZipReader> {
+        let reader = unsafe { ZipReaderPtr::from_ptr(cef_zip_reader_create(stream.into_raw())) }?;
+        Some(ZipReader {
+            reader,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Moves the cursor to the first file in the archive. Returns true if the
+    /// cursor position was set successfully.
+    pub fn move_to_first_file(&mut self) -> bool {
+        unsafe { self.reader.0.move_to_first_file.unwrap()(self.reader.as_ptr()) != 0 }
+    }
+    /// Moves the cursor to the next file in the archive. Returns true if the
+    /// cursor position was set successfully.
+    pub fn move_to_next_file(&mut self) -> bool {
+        unsafe { self.reader.0.move_to_next_file.unwrap()(self.reader.as_ptr()) != 0 }
+    }
+    /// Moves the cursor to the specified file in the archive. If `case_sensitive`
+    /// is true then the search will be case sensitive. Returns true if the cursor
+    /// position was set successfully.
+    pub fn move_to_file(&mut self, file_name: &str, case_sensitive: bool) -> bool {
+        unsafe {
+            self.reader.0.move_to_file.unwrap()(
+                self.reader.as_ptr(),
+                CefString::new(file_name).as_ptr(),
+                case_sensitive as _,
+            ) != 0
+        }
+    }
+
+    /// Returns the name of the file.
+    pub fn get_file_name(&self) -> String {
+        unsafe { CefString::from_userfree(self.reader.0.get_file_name.unwrap()(self.reader.as_ptr())) }
+            .map(String::from)
+            .unwrap_or_default()
+    }
+    /// Returns the uncompressed size of the file.
+    pub fn get_file_size(&self) -> u64 {
+        unsafe { self.reader.0.get_file_size.unwrap()(self.reader.as_ptr()).max(0) as u64 }
+    }
+    /// Returns the last modified timestamp for the file, or None if the archive
+    /// doesn't have a valid one.
+    pub fn get_file_last_modified(&self) -> Option<DateTime<Utc>> {
+        let modified = unsafe { self.reader.0.get_file_last_modified.unwrap()(self.reader.as_ptr()) };
+        if (1..=12).contains(&modified.month) && (1..=31).contains(&modified.day_of_month) {
+            Some(cef_time_to_date_time(modified))
+        } else {
+            None
+        }
+    }
+    /// Returns the name, size and last modified timestamp of the file.
+    pub fn get_entry(&self) -> ZipEntry {
+        ZipEntry {
+            name: self.get_file_name(),
+            size: self.get_file_size(),
+            modified: self.get_file_last_modified(),
+        }
+    }
+    /// Returns an iterator over the files in the archive. The cursor is left at
+    /// the last file that was returned.
+    pub fn entries(&mut self) -> ZipEntries<'_> {
+        ZipEntries {
+            reader: self,
+            started: false,
+        }
+    }
+
+    /// Opens the file for reading of uncompressed data. A read password may
+    /// optionally be specified. Returns true if the file was opened.
+    pub fn open_file(&mut self, password: Option<&str>) -> bool {
+        let password = password.map(CefString::new);
+        unsafe {
+            self.reader.0.open_file.unwrap()(
+                self.reader.as_ptr(),
+                password.as_ref().map(|password| password.as_ptr()).unwrap_or_else(null),
+            ) != 0
+        }
+    }
+    /// Closes the file. Returns false if the file wasn't open or its contents
+    /// didn't match its checksum.
+    pub fn close_file(&mut self) -> bool {
+        unsafe { self.reader.0.close_file.unwrap()(self.reader.as_ptr()) != 0 }
+    }
+    /// Read uncompressed file contents into `buffer`, returning the number of
+    /// bytes read, which is 0 at the end of the file.
+    pub fn read_file(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = unsafe {
+            self.reader.0.read_file.unwrap()(self.reader.as_ptr(), buffer.as_mut_ptr() as *mut c_void, buffer.len())
+        };
+        if read < 0 {
+            Err(io::Error::new(io::ErrorKind::InvalidData, format!("failed to read {} from zip archive", self.get_file_name())))
+        } else {
+            Ok(read as usize)
+        }
+    }
+    /// Returns the current offset in the uncompressed file contents.
+    pub fn tell(&self) -> u64 {
+        unsafe { self.reader.0.tell.unwrap()(self.reader.as_ptr()).max(0) as u64 }
+    }
+    /// Returns true if at end of the file contents.
+    pub fn eof(&self) -> bool {
+        unsafe { self.reader.0.eof.unwrap()(self.reader.as_ptr()) != 0 }
+    }
+}
+
+/// Reads the file opened with [ZipReader::open_file].
+impl Read for ZipReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_file(buf)
+    }
+}
+
+impl Drop for ZipReader {
+    /// Closes the archive on the thread the reader was used on.
+    fn drop(&mut self) {
+        if let Some(close) = self.reader.0.close {
+            unsafe { close(self.reader.as_ptr()) };
+        }
+    }
+}
+
+/// An iterator over the files in a zip archive, created by
+/// [ZipReader::entries].
+pub struct ZipEntries<'a> {
+    reader: &'a mut ZipReader,
+    started: bool,
+}
+
+impl Iterator for ZipEntries<'_> {
+    type Item = ZipEntry;
+
+    fn next(&mut self) -> Option<ZipEntry> {
+        let moved = if self.started {
+            self.reader.move_to_next_file()
+        } else {
+            self.started = true;
+            self.reader.move_to_first_file()
+        };
+        if moved {
+            Some(self.reader.get_entry())
+        } else {
+            None
+        }
+    }
+}